@@ -1,14 +1,28 @@
 //! Interface for users to interact with this application
 //! Each command will query the server (via the client), and return the result
 use clap::{Args, Parser, Subcommand};
-use serde::Deserialize;
-use std::{fmt::Write, fs::File, io::BufReader};
+use serde::de::DeserializeOwned;
+use std::{
+    fmt,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use crate::client;
+use crate::{client, interactive, tui};
 
 use estimate_common::{
-    common::{TaxInfo, TaxResults},
+    common::{GrossIncomeSolution, MarriageAnalysis, TaxInfo, TaxResults},
     errors::{EstimaterErrors, EstimaterResult},
+    estimated_payments::{
+        EstimatedPaymentPlan, SafeHarborInfo, UnderpaymentInfo, UnderpaymentPenalty,
+    },
+    file_format::FileFormat,
+    paycheck::{PaycheckInfo, PaycheckSchedule, PaycheckScheduleInfo, PaycheckSolution},
+    projection::{Projection, ProjectionInfo},
+    retirement::{AnnualAdditions, RetirementPlanInfo},
+    scenarios::{ScenarioComparison, ScenarioFile},
+    w4::{W4Analysis, W4Info},
 };
 
 /// Simple program to greet a person
@@ -17,16 +31,89 @@ use estimate_common::{
 pub(crate) struct EstimateCli {
     #[clap(subcommand)]
     command: EstimateCommands,
+    /// Directory searched first for tax data files (i.e. federal_tax_bracket.json). Falls back
+    /// to $ESTIMATE_DATA_DIR, then $XDG_CONFIG_HOME/estimate, then the built in data.
+    #[clap(long = "data-dir", global = true)]
+    data_dir: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Clone, Debug)]
 enum EstimateCommands {
-    /// Path to data file (in json format) representing what to process
+    /// Path to data file (json, toml or yaml) representing what to process
     Config(FromConfigStruct),
 
     /// Manually input data via command line flags
     #[clap(name = "input")]
-    CliArgs(TaxInfo),
+    CliArgs(Box<TaxInfo>),
+
+    /// Answer guided questions, with a running estimate, and optionally save them as a config
+    Interactive,
+
+    /// Full screen UI where changing the inputs immediately updates the estimate
+    Tui(TuiStruct),
+
+    /// Find the gross yearly income needed to take home a target net income
+    GrossForNet(GrossForNetStruct),
+
+    /// Find the gross yearly income implied by the net pay of a paycheck
+    PaycheckToGross(PaycheckToGrossStruct),
+
+    /// List every paycheck of the year with its withholding, Social Security and Medicare
+    PaycheckSchedule(PaycheckScheduleStruct),
+
+    /// Compare a W-4's federal withholding to the year's tax, and recommend the Step 4(c) extra
+    W4(W4Struct),
+
+    /// Compare 401(k) contributions to the year's limits, and find the after-tax room left
+    AfterTaxRoom(AfterTaxRoomStruct),
+
+    /// Project the taxes and net income of each of the coming years as pay grows
+    Projection(ProjectionStruct),
+
+    /// Compare two people's taxes filing as single against filing as a married couple
+    Marriage(MarriageStruct),
+
+    /// Plan the quarterly estimated payments needed to meet the safe harbor rules
+    EstimatedPayments(EstimatedPaymentsStruct),
+
+    /// Estimate the penalty for underpaying taxes during the year
+    UnderpaymentPenalty(UnderpaymentPenaltyStruct),
+
+    /// Compare a base scenario against named variants of it
+    Scenarios(ScenariosStruct),
+}
+
+/// The result of running any of the commands
+enum CommandOutput {
+    Taxes(TaxResults),
+    GrossForNet(GrossIncomeSolution),
+    PaycheckToGross(PaycheckSolution),
+    PaycheckSchedule(PaycheckSchedule),
+    W4(W4Analysis),
+    AfterTaxRoom(AnnualAdditions),
+    Projection(Projection),
+    Marriage(Box<MarriageAnalysis>),
+    EstimatedPayments(EstimatedPaymentPlan),
+    UnderpaymentPenalty(UnderpaymentPenalty),
+    Scenarios(ScenarioComparison),
+}
+
+impl fmt::Display for CommandOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandOutput::Taxes(tax_results) => write!(f, "{tax_results}"),
+            CommandOutput::GrossForNet(solution) => write!(f, "{solution}"),
+            CommandOutput::PaycheckToGross(solution) => write!(f, "{solution}"),
+            CommandOutput::PaycheckSchedule(schedule) => write!(f, "{schedule}"),
+            CommandOutput::W4(analysis) => write!(f, "{analysis}"),
+            CommandOutput::AfterTaxRoom(additions) => write!(f, "{additions}"),
+            CommandOutput::Projection(projection) => write!(f, "{projection}"),
+            CommandOutput::Marriage(analysis) => write!(f, "{analysis}"),
+            CommandOutput::EstimatedPayments(plan) => write!(f, "{plan}"),
+            CommandOutput::UnderpaymentPenalty(penalty) => write!(f, "{penalty}"),
+            CommandOutput::Scenarios(comparison) => write!(f, "{comparison}"),
+        }
+    }
 }
 
 #[derive(Args, Clone, Debug)]
@@ -34,56 +121,167 @@ struct FromConfigStruct {
     path_to_file: String,
 }
 
+#[derive(Args, Clone, Debug)]
+struct GrossForNetStruct {
+    /// Path to the config file with the rest of the tax info. Its gross_yearly_income is ignored.
+    path_to_file: String,
+    /// The yearly net (take home) income to reach
+    #[clap(long = "net")]
+    target_net_income: f64,
+}
+
+#[derive(Args, Clone, Debug)]
+struct PaycheckToGrossStruct {
+    /// Path to the config file with the filing status and state tax rate. Its
+    /// gross_yearly_income and yearly pre_tax_deducations are ignored.
+    path_to_file: String,
+    /// The net pay of a single regular paycheck
+    #[clap(long = "net-pay")]
+    target_net_pay: f64,
+    #[clap(flatten)]
+    paycheck_info: PaycheckInfo,
+}
+
+#[derive(Args, Clone, Debug)]
+struct PaycheckScheduleStruct {
+    /// Path to the config file of the year
+    path_to_file: String,
+    #[clap(flatten)]
+    schedule_info: PaycheckScheduleInfo,
+}
+
+#[derive(Args, Clone, Debug)]
+struct W4Struct {
+    /// Path to the config file of the year
+    path_to_file: String,
+    #[clap(flatten)]
+    w4_info: W4Info,
+}
+
+#[derive(Args, Clone, Debug)]
+struct AfterTaxRoomStruct {
+    /// Path to the config file of the year
+    path_to_file: String,
+    #[clap(flatten)]
+    plan_info: RetirementPlanInfo,
+}
+
+#[derive(Args, Clone, Debug)]
+struct ProjectionStruct {
+    /// Path to the config file of the first year
+    path_to_file: String,
+    #[clap(flatten)]
+    projection_info: ProjectionInfo,
+}
+
+#[derive(Args, Clone, Debug)]
+struct MarriageStruct {
+    /// Path to the config file of the first person
+    first_path_to_file: String,
+    /// Path to the config file of the second person
+    second_path_to_file: String,
+}
+
+#[derive(Args, Clone, Debug)]
+struct EstimatedPaymentsStruct {
+    /// Path to the config file of the current year
+    path_to_file: String,
+    #[clap(flatten)]
+    safe_harbor_info: SafeHarborInfo,
+}
+
+#[derive(Args, Clone, Debug)]
+struct UnderpaymentPenaltyStruct {
+    /// Path to the config file of the year
+    path_to_file: String,
+    /// Path to a json, toml or yaml file with the required annual payment and the payments made
+    path_to_payments_file: String,
+}
+
+#[derive(Args, Clone, Debug)]
+struct TuiStruct {
+    /// Path to the config file to load, and to save to. Starts empty when it does not exist.
+    path_to_file: Option<String>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct ScenariosStruct {
+    /// Path to a json, toml or yaml file with a base scenario and its variants
+    path_to_file: String,
+}
+
 impl FromConfigStruct {
+    fn new(path_to_file: &str) -> Self {
+        Self {
+            path_to_file: path_to_file.to_string(),
+        }
+    }
+
     /// Validates the path
     ///
     /// # Return
     ///
     /// * The parsed config file as TaxInfo, or an error.
     fn validate_config_file(&self) -> EstimaterResult<TaxInfo> {
-        let file = File::open(&self.path_to_file);
-        match file {
-            Err(err) => {
-                let mut err_msg = format!(
-                    "Incorrect path {} provided. File does not exist.",
-                    &self.path_to_file
-                );
-                write!(err_msg, "\n{:?}", err)
-                    .expect("Writting the error message for validating config failed");
-                Err(EstimaterErrors::UserError(err_msg))
-            }
-            Ok(file) => {
-                let file_reader = BufReader::new(file);
-                Self::parse_config(file_reader)
-            }
-        }
+        let contents = read_file_contents(&self.path_to_file)?;
+        Self::parse_config(&self.path_to_file, &contents)
     }
 
-    /// Parses the config file and attempts to convert it to a known struct
-    fn parse_config(file_reader: BufReader<File>) -> EstimaterResult<TaxInfo> {
-        let mut de = serde_json::Deserializer::from_reader(file_reader);
-        let contents = TaxInfo::deserialize(&mut de);
+    /// Parses the config file (json, toml or yaml by its extension) and attempts to convert it
+    /// to a known struct
+    fn parse_config(path_to_file: &str, contents: &str) -> EstimaterResult<TaxInfo> {
+        let contents = FileFormat::from_path(Path::new(path_to_file)).parse::<TaxInfo>(contents);
         match contents {
             Ok(tax_info) => Ok(tax_info),
             Err(err) => {
                 let mut msg =
                     "The config file does not contain at LEAST one of the following: ".to_string();
-                write!(
-                    msg,
-                    "gross_yearly_income, federal_tax_rate_percent, state_tax_rate_percent"
-                )
-                .unwrap();
-                write!(msg, "\nError: {err}").unwrap();
+                write!(msg, "state_tax_rate_percent").unwrap();
+                write!(msg, "\nError: {}", parsing_error_message(err)).unwrap();
                 Err(EstimaterErrors::ParsingError(msg))
             }
         }
     }
 }
 
+/// Reads the contents of a file given by the user
+fn read_file_contents(path_to_file: &str) -> EstimaterResult<String> {
+    fs::read_to_string(path_to_file).map_err(|err| {
+        let mut err_msg = format!(
+            "Incorrect path {} provided. File does not exist.",
+            path_to_file
+        );
+        write!(err_msg, "\n{:?}", err)
+            .expect("Writting the error message for validating config failed");
+        EstimaterErrors::UserError(err_msg)
+    })
+}
+
+/// Reads a json, toml or yaml file (by its extension) into the given struct
+fn read_data_file<T: DeserializeOwned>(path_to_file: &str) -> EstimaterResult<T> {
+    let contents = read_file_contents(path_to_file)?;
+    FileFormat::from_path(Path::new(path_to_file))
+        .parse(&contents)
+        .map_err(|err| {
+            EstimaterErrors::ParsingError(format!(
+                "Could not parse {path_to_file}.\nError: {}",
+                parsing_error_message(err)
+            ))
+        })
+}
+
+/// Gets the message of a parsing error without its description of the error type
+fn parsing_error_message(err: EstimaterErrors) -> String {
+    match err {
+        EstimaterErrors::ParsingError(msg) => msg,
+        err => err.to_string(),
+    }
+}
+
 impl EstimateCommands {
     /// Runs the commands after parsing
-    pub fn run_command(cmd: EstimateCommands) -> EstimaterResult<TaxResults> {
-        let info = match cmd {
+    fn run_command(cmd: EstimateCommands) -> EstimaterResult<CommandOutput> {
+        match cmd {
             EstimateCommands::Config(from_config_struct) => {
                 // TODO - read in from a config file path'd
                 println!(
@@ -92,27 +290,115 @@ impl EstimateCommands {
                 );
                 let tax_info: TaxInfo = from_config_struct.validate_config_file()?;
                 println!("{}", tax_info);
-                tax_info
+                client::calculate_taxes(tax_info).map(CommandOutput::Taxes)
             }
             EstimateCommands::CliArgs(tax_info) => {
                 println!("{}", tax_info);
-                tax_info
+                client::calculate_taxes(*tax_info).map(CommandOutput::Taxes)
+            }
+            EstimateCommands::Interactive => {
+                interactive::run_interactive().map(CommandOutput::Taxes)
+            }
+            EstimateCommands::Tui(tui_struct) => {
+                let path_to_file = tui_struct
+                    .path_to_file
+                    .unwrap_or_else(|| interactive::DEFAULT_SAVE_PATH.to_string());
+                let tax_info = if Path::new(&path_to_file).exists() {
+                    FromConfigStruct::new(&path_to_file).validate_config_file()?
+                } else {
+                    TaxInfo::default()
+                };
+                tui::run_tui(tax_info, PathBuf::from(path_to_file)).map(CommandOutput::Taxes)
+            }
+            EstimateCommands::GrossForNet(gross_struct) => {
+                let tax_info =
+                    FromConfigStruct::new(&gross_struct.path_to_file).validate_config_file()?;
+                println!("{}", tax_info);
+                client::solve_gross_income(tax_info, gross_struct.target_net_income)
+                    .map(CommandOutput::GrossForNet)
+            }
+            EstimateCommands::PaycheckToGross(paycheck_struct) => {
+                let tax_info =
+                    FromConfigStruct::new(&paycheck_struct.path_to_file).validate_config_file()?;
+                println!("{}", tax_info);
+                client::solve_gross_from_paycheck(
+                    tax_info,
+                    paycheck_struct.paycheck_info,
+                    paycheck_struct.target_net_pay,
+                )
+                .map(CommandOutput::PaycheckToGross)
+            }
+            EstimateCommands::PaycheckSchedule(schedule_struct) => {
+                let tax_info =
+                    FromConfigStruct::new(&schedule_struct.path_to_file).validate_config_file()?;
+                println!("{}", tax_info);
+                client::build_paycheck_schedule(tax_info, schedule_struct.schedule_info)
+                    .map(CommandOutput::PaycheckSchedule)
+            }
+            EstimateCommands::W4(w4_struct) => {
+                let tax_info =
+                    FromConfigStruct::new(&w4_struct.path_to_file).validate_config_file()?;
+                println!("{}", tax_info);
+                client::analyze_w4(tax_info, w4_struct.w4_info).map(CommandOutput::W4)
+            }
+            EstimateCommands::AfterTaxRoom(after_tax_room_struct) => {
+                let tax_info = FromConfigStruct::new(&after_tax_room_struct.path_to_file)
+                    .validate_config_file()?;
+                println!("{}", tax_info);
+                client::analyze_annual_additions(tax_info, after_tax_room_struct.plan_info)
+                    .map(CommandOutput::AfterTaxRoom)
+            }
+            EstimateCommands::Projection(projection_struct) => {
+                let tax_info = FromConfigStruct::new(&projection_struct.path_to_file)
+                    .validate_config_file()?;
+                println!("{}", tax_info);
+                client::project_taxes(tax_info, projection_struct.projection_info)
+                    .map(CommandOutput::Projection)
+            }
+            EstimateCommands::Marriage(marriage_struct) => {
+                let first = FromConfigStruct::new(&marriage_struct.first_path_to_file)
+                    .validate_config_file()?;
+                let second = FromConfigStruct::new(&marriage_struct.second_path_to_file)
+                    .validate_config_file()?;
+                println!("{}\n{}", first, second);
+                client::analyze_marriage(first, second)
+                    .map(|analysis| CommandOutput::Marriage(Box::new(analysis)))
+            }
+            EstimateCommands::EstimatedPayments(payments_struct) => {
+                let tax_info =
+                    FromConfigStruct::new(&payments_struct.path_to_file).validate_config_file()?;
+                println!("{}", tax_info);
+                client::plan_estimated_payments(tax_info, payments_struct.safe_harbor_info)
+                    .map(CommandOutput::EstimatedPayments)
+            }
+            EstimateCommands::UnderpaymentPenalty(penalty_struct) => {
+                let tax_info =
+                    FromConfigStruct::new(&penalty_struct.path_to_file).validate_config_file()?;
+                let underpayment_info: UnderpaymentInfo =
+                    read_data_file(&penalty_struct.path_to_payments_file)?;
+                println!("{}", tax_info);
+                client::estimate_underpayment_penalty(tax_info, underpayment_info)
+                    .map(CommandOutput::UnderpaymentPenalty)
+            }
+            EstimateCommands::Scenarios(scenarios_struct) => {
+                let scenario_file: ScenarioFile = read_data_file(&scenarios_struct.path_to_file)?;
+                client::compare_scenarios(scenario_file).map(CommandOutput::Scenarios)
             }
-        };
-        client::calculate_taxes(info)
+        }
     }
 }
 /// Entrance to the client by parsing CLI values and running commands
 pub(crate) fn run_cli() {
     let args = EstimateCli::parse();
-    let cmd_res: EstimaterResult<TaxResults> = EstimateCommands::run_command(args.command);
+    let cmd_res: EstimaterResult<CommandOutput> = client::set_data_dir(args.data_dir)
+        .and_then(|_| EstimateCommands::run_command(args.command));
 
     match cmd_res {
         Err(err) => {
             println!("Error Running command : <print cmd>.\n Error: {}", err);
         }
-        Ok(tax_results) => {
-            println!("{}", tax_results);
+        Ok(output) => {
+            println!("{}", output);
         }
     }
 }
@@ -1,10 +1,108 @@
 use estimate_common::{
-    common::{TaxInfo, TaxResults},
+    common::{BracketBreakdown, GrossIncomeSolution, MarriageAnalysis, TaxInfo, TaxResults},
     errors::EstimaterResult,
+    estimated_payments::{
+        EstimatedPaymentPlan, SafeHarborInfo, UnderpaymentInfo, UnderpaymentPenalty,
+    },
+    paycheck::{PaycheckInfo, PaycheckSchedule, PaycheckScheduleInfo, PaycheckSolution},
+    projection::{Projection, ProjectionInfo},
+    retirement::{AnnualAdditions, RetirementPlanInfo},
+    scenarios::{ScenarioComparison, ScenarioFile},
+    w4::{W4Analysis, W4Info},
 };
-use estimate_server::server;
+use estimate_server::{
+    data, estimated_payments, marriage, paycheck, projection, retirement, reverse, scenarios,
+    server, underpayment, w4,
+};
+use std::path::PathBuf;
+
+/// Sets the directory searched first for the server's data files
+pub fn set_data_dir(data_dir: Option<PathBuf>) -> EstimaterResult<()> {
+    data::set_data_dir(data_dir)
+}
 
-/// Computes taxes given the needed info
+/// Computes taxes given the needed info, taking the standard deduction of the filing status.
+/// Withholding assumes it is taken, so the refunds are only right with it.
 pub fn calculate_taxes(info: TaxInfo) -> EstimaterResult<TaxResults> {
-    server::calculate_taxes(&info)
+    server::calculate_taxes_with_standard_deduction(&info)
+}
+
+/// Splits the federal taxable income across the federal brackets, after the standard deduction
+/// like `calculate_taxes`
+pub fn calculate_federal_bracket_breakdown(
+    info: TaxInfo,
+) -> EstimaterResult<Vec<BracketBreakdown>> {
+    server::calculate_federal_bracket_breakdown_with_standard_deduction(&info)
+}
+
+/// Finds the gross yearly income needed to take home the target net income
+pub fn solve_gross_income(
+    info: TaxInfo,
+    target_net_income: f64,
+) -> EstimaterResult<GrossIncomeSolution> {
+    reverse::solve_gross_income(&info, target_net_income)
+}
+
+/// Finds the gross yearly income implied by the net pay of a paycheck
+pub fn solve_gross_from_paycheck(
+    info: TaxInfo,
+    paycheck_info: PaycheckInfo,
+    target_net_pay: f64,
+) -> EstimaterResult<PaycheckSolution> {
+    reverse::solve_gross_from_paycheck(&info, &paycheck_info, target_net_pay)
+}
+
+/// Lists every regular paycheck over a tax year
+pub fn build_paycheck_schedule(
+    info: TaxInfo,
+    schedule_info: PaycheckScheduleInfo,
+) -> EstimaterResult<PaycheckSchedule> {
+    paycheck::build_paycheck_schedule(&info, &schedule_info)
+}
+
+/// Compares the federal withholding of a W-4 to the year's federal tax
+pub fn analyze_w4(info: TaxInfo, w4_info: W4Info) -> EstimaterResult<W4Analysis> {
+    w4::analyze_w4(&info, &w4_info)
+}
+
+/// Compares the 401(k) contributions to the plan year's limits, and finds the after-tax room left
+pub fn analyze_annual_additions(
+    info: TaxInfo,
+    plan_info: RetirementPlanInfo,
+) -> EstimaterResult<AnnualAdditions> {
+    retirement::analyze_annual_additions(&info, &plan_info)
+}
+
+/// Projects the taxes and net income of each of the coming years
+pub fn project_taxes(
+    info: TaxInfo,
+    projection_info: ProjectionInfo,
+) -> EstimaterResult<Projection> {
+    projection::project_taxes(&info, &projection_info)
+}
+
+/// Compares the taxes of two people filing single against filing as a married couple
+pub fn analyze_marriage(first: TaxInfo, second: TaxInfo) -> EstimaterResult<MarriageAnalysis> {
+    marriage::analyze_marriage(&first, &second)
+}
+
+/// Plans the quarterly estimated payments that satisfy the safe harbor rules
+pub fn plan_estimated_payments(
+    info: TaxInfo,
+    safe_harbor_info: SafeHarborInfo,
+) -> EstimaterResult<EstimatedPaymentPlan> {
+    estimated_payments::plan_estimated_payments(&info, &safe_harbor_info)
+}
+
+/// Estimates the penalty for underpaying taxes over the year
+pub fn estimate_underpayment_penalty(
+    info: TaxInfo,
+    underpayment_info: UnderpaymentInfo,
+) -> EstimaterResult<UnderpaymentPenalty> {
+    underpayment::estimate_underpayment_penalty(&info, &underpayment_info)
+}
+
+/// Compares the taxes of a base scenario against each of its variants
+pub fn compare_scenarios(scenario_file: ScenarioFile) -> EstimaterResult<ScenarioComparison> {
+    scenarios::compare_scenarios(&scenario_file)
 }
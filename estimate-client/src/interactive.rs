@@ -0,0 +1,400 @@
+//! Guided prompts that walk a user through entering their tax info, one topic at a time.
+//! Shows a running estimate after each topic, and can save the answers as a config file.
+use chrono::NaiveDate;
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use std::path::Path;
+
+use crate::client;
+
+use estimate_common::{
+    common::{FilingStatus, HourlyWage, TaxInfo, TaxResults},
+    deductions::{DeductionType, PreTaxDeduction},
+    employment::Employment,
+    equity::RsuVest,
+    errors::{EstimaterErrors, EstimaterResult},
+    file_format::write_file,
+};
+
+/// The file answers are saved to unless the user picks another one
+pub(crate) const DEFAULT_SAVE_PATH: &str = "tax_info.toml";
+
+/// The pre-tax deductions asked for, each with its prompt
+const DEDUCTION_PROMPTS: [(DeductionType, &str); 7] = [
+    (
+        DeductionType::Traditional401k,
+        "Traditional (not Roth) 401(k) contributions",
+    ),
+    (
+        DeductionType::Traditional403b,
+        "Traditional (not Roth) 403(b) contributions",
+    ),
+    (
+        DeductionType::Governmental457b,
+        "Traditional (not Roth) governmental 457(b) contributions",
+    ),
+    (DeductionType::Hsa, "HSA contributions made through payroll"),
+    (
+        DeductionType::HealthPremiums,
+        "Pre-tax health, dental and vision premiums",
+    ),
+    (
+        DeductionType::CommuterBenefits,
+        "Commuter benefits (transit and parking)",
+    ),
+    (
+        DeductionType::DependentCareFsa,
+        "Dependent care FSA contributions",
+    ),
+];
+
+/// Prompts for every part of the tax info, then calculates the taxes.
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when the prompts could not be shown or answered
+/// * `Ok(TaxResults)`: The taxes for the answers given
+pub(crate) fn run_interactive() -> EstimaterResult<TaxResults> {
+    let theme = ColorfulTheme::default();
+    let mut tax_info = TaxInfo::default();
+
+    println!("Filing status");
+    tax_info.filing_status = prompt_filing_status(&theme)?;
+    show_running_estimate(&tax_info);
+
+    println!("\nIncome");
+    tax_info.gross_yearly_income = Input::<u64>::with_theme(&theme)
+        .with_prompt("Yearly salary, before any deductions (0 if only paid by the hour)")
+        .interact_text()
+        .map_err(prompt_error)?;
+    if confirm(&theme, "Are you paid by the hour?")? {
+        tax_info.hourly = prompt_hourly_wage(&theme)?;
+    }
+    if confirm(
+        &theme,
+        "Did you have other jobs this year, i.e. one you left mid-year?",
+    )? {
+        tax_info.employments = prompt_employments(&theme)?;
+    }
+    tax_info.bonus_income = prompt_amount(
+        &theme,
+        "Bonuses, commissions and other supplemental wages",
+        f64::MAX,
+    )?;
+    if confirm(&theme, "Do you have RSUs vesting this year?")? {
+        tax_info.rsu_vests = prompt_rsu_vests(&theme)?;
+    }
+    show_running_estimate(&tax_info);
+
+    println!("\nDeductions and contributions");
+    println!(
+        "Pre-tax deductions are taken out of your pay before income tax, so they lower your \
+         taxable income. Enter yearly amounts, or 0 for none."
+    );
+    let mut remaining_wages = tax_info.total_wages();
+    for (deduction_type, prompt) in DEDUCTION_PROMPTS {
+        let amount = prompt_amount(&theme, prompt, remaining_wages)?;
+        if amount > 0.0 {
+            tax_info
+                .deductions
+                .push(PreTaxDeduction::new(deduction_type, amount));
+        }
+        remaining_wages -= amount;
+    }
+    println!("Contributions taken out of your pay after income tax do not lower it.");
+    tax_info.roth_deferrals = prompt_amount(
+        &theme,
+        "Roth 401(k) / 403(b) contributions",
+        remaining_wages,
+    )?;
+    remaining_wages -= tax_info.roth_deferrals;
+    tax_info.after_tax_contributions = prompt_amount(
+        &theme,
+        "After-tax (not Roth) 401(k) contributions, i.e. for a mega-backdoor Roth",
+        remaining_wages,
+    )?;
+    show_running_estimate(&tax_info);
+
+    println!("\nState");
+    tax_info.state_tax_rate_percent = Input::<f64>::with_theme(&theme)
+        .with_prompt("State income tax rate, as a % (0 for none)")
+        .default(0.0)
+        .validate_with(|rate: &f64| validate_rate(*rate))
+        .interact_text()
+        .map_err(prompt_error)?;
+    show_running_estimate(&tax_info);
+
+    println!("\nWithholding");
+    if confirm(
+        &theme,
+        "Have taxes been, or will they be, withheld from your pay?",
+    )? {
+        tax_info.federal_withholding_ytd = prompt_amount(
+            &theme,
+            "Federal tax withheld so far this year from regular pay. Leave out the withholding on \
+             bonuses and RSUs, which is estimated separately, and on the other jobs",
+            f64::MAX,
+        )?;
+        tax_info.federal_withholding_projected = prompt_amount(
+            &theme,
+            "Federal tax expected to be withheld for the rest of the year",
+            f64::MAX,
+        )?;
+        tax_info.state_withholding_ytd = prompt_amount(
+            &theme,
+            "State tax withheld so far this year, leaving out the other jobs",
+            f64::MAX,
+        )?;
+        tax_info.state_withholding_projected = prompt_amount(
+            &theme,
+            "State tax expected to be withheld for the rest of the year",
+            f64::MAX,
+        )?;
+    }
+
+    println!("\n{tax_info}");
+    if confirm(&theme, "Save these answers as a config file?")? {
+        save_answers(&theme, &tax_info)?;
+    }
+    client::calculate_taxes(tax_info)
+}
+
+/// Asks for the filing status
+fn prompt_filing_status(theme: &ColorfulTheme) -> EstimaterResult<FilingStatus> {
+    let filing_statuses = [
+        FilingStatus::Single,
+        FilingStatus::MarriedFilingJointly,
+        FilingStatus::MarriedFilingSeparately,
+    ];
+    let selected = Select::with_theme(theme)
+        .with_prompt("How will you file?")
+        .items(&filing_statuses)
+        .default(0)
+        .interact()
+        .map_err(prompt_error)?;
+    Ok(filing_statuses[selected])
+}
+
+/// Asks for the hourly rate and the hours worked
+fn prompt_hourly_wage(theme: &ColorfulTheme) -> EstimaterResult<HourlyWage> {
+    let default = HourlyWage::default();
+    Ok(HourlyWage {
+        hourly_rate: prompt_amount(theme, "Pay per regular hour", f64::MAX)?,
+        hours_per_week: prompt_amount_or(
+            theme,
+            "Regular hours worked a week",
+            default.hours_per_week,
+            168.0,
+        )?,
+        weeks_per_year: prompt_amount_or(
+            theme,
+            "Weeks worked a year",
+            default.weeks_per_year,
+            53.0,
+        )?,
+        overtime_hours_per_week: prompt_amount(theme, "Overtime hours worked a week", 168.0)?,
+        overtime_multiplier: prompt_amount_or(
+            theme,
+            "Multiple of the hourly rate paid for overtime",
+            default.overtime_multiplier,
+            f64::MAX,
+        )?,
+    })
+}
+
+/// Asks for each of the other jobs held over the year, as reported on their W-2s
+fn prompt_employments(theme: &ColorfulTheme) -> EstimaterResult<Vec<Employment>> {
+    let mut employments = Vec::new();
+    loop {
+        let employer: String = Input::with_theme(theme)
+            .with_prompt("Employer")
+            .interact_text()
+            .map_err(prompt_error)?;
+        let start_date = prompt_date(theme, "First day worked (YYYY-MM-DD)", None)?;
+        let end_date = prompt_date(theme, "Last day worked (YYYY-MM-DD)", Some(start_date))?;
+        employments.push(Employment {
+            employer,
+            start_date,
+            end_date,
+            wages: prompt_amount(theme, "Wages paid (W-2 box 1)", f64::MAX)?,
+            federal_withholding: prompt_amount(
+                theme,
+                "Federal tax withheld (W-2 box 2)",
+                f64::MAX,
+            )?,
+            state_withholding: prompt_amount(theme, "State tax withheld (W-2 box 17)", f64::MAX)?,
+            social_security_withheld: None,
+            medicare_withheld: None,
+        });
+        if !confirm(theme, "Add another job?")? {
+            return Ok(employments);
+        }
+    }
+}
+
+/// Asks for each RSU vest over the year
+fn prompt_rsu_vests(theme: &ColorfulTheme) -> EstimaterResult<Vec<RsuVest>> {
+    let mut rsu_vests = Vec::new();
+    loop {
+        rsu_vests.push(RsuVest {
+            vest_date: prompt_date(theme, "Vest date (YYYY-MM-DD)", None)?,
+            shares: Input::<u64>::with_theme(theme)
+                .with_prompt("Shares vesting")
+                .interact_text()
+                .map_err(prompt_error)?,
+            price: prompt_amount(theme, "Expected share price on the vest date", f64::MAX)?,
+        });
+        if !confirm(theme, "Add another vest?")? {
+            return Ok(rsu_vests);
+        }
+    }
+}
+
+/// Asks for a date, which must not be before `earliest` when given
+fn prompt_date(
+    theme: &ColorfulTheme,
+    prompt: &str,
+    earliest: Option<NaiveDate>,
+) -> EstimaterResult<NaiveDate> {
+    Input::<NaiveDate>::with_theme(theme)
+        .with_prompt(prompt)
+        .validate_with(move |date: &NaiveDate| validate_date(*date, earliest))
+        .interact_text()
+        .map_err(prompt_error)
+}
+
+/// Asks for a dollar amount between 0 and `max`, defaulting to 0
+fn prompt_amount(theme: &ColorfulTheme, prompt: &str, max: f64) -> EstimaterResult<f64> {
+    prompt_amount_or(theme, prompt, 0.0, max)
+}
+
+/// Asks for an amount between 0 and `max`, defaulting to `default`
+fn prompt_amount_or(
+    theme: &ColorfulTheme,
+    prompt: &str,
+    default: f64,
+    max: f64,
+) -> EstimaterResult<f64> {
+    Input::<f64>::with_theme(theme)
+        .with_prompt(prompt)
+        .default(default)
+        .validate_with(move |amount: &f64| validate_amount(*amount, max))
+        .interact_text()
+        .map_err(prompt_error)
+}
+
+/// Checks an amount is a number from 0 to `max`
+fn validate_amount(amount: f64, max: f64) -> Result<(), String> {
+    if !amount.is_finite() || amount < 0.0 {
+        Err("Enter an amount of 0 or more".to_string())
+    } else if amount > max {
+        Err(format!("Enter an amount no more than {max}"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks a tax rate is a percentage
+fn validate_rate(rate: f64) -> Result<(), &'static str> {
+    if (0.0..=100.0).contains(&rate) {
+        Ok(())
+    } else {
+        Err("Enter a rate between 0 and 100")
+    }
+}
+
+/// Checks a date is not before `earliest`, when given
+fn validate_date(date: NaiveDate, earliest: Option<NaiveDate>) -> Result<(), String> {
+    match earliest {
+        Some(earliest) if date < earliest => Err(format!("Enter a date on or after {earliest}")),
+        _ => Ok(()),
+    }
+}
+
+/// Asks a yes / no question, defaulting to no
+fn confirm(theme: &ColorfulTheme, prompt: &str) -> EstimaterResult<bool> {
+    Confirm::with_theme(theme)
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .map_err(prompt_error)
+}
+
+/// Asks where to save the answers, then writes them in the format of the file's extension
+fn save_answers(theme: &ColorfulTheme, tax_info: &TaxInfo) -> EstimaterResult<()> {
+    let path_to_file: String = Input::with_theme(theme)
+        .with_prompt("Path to save to (.toml, .yaml or .json)")
+        .default(DEFAULT_SAVE_PATH.to_string())
+        .interact_text()
+        .map_err(prompt_error)?;
+    write_file(Path::new(&path_to_file), tax_info)?;
+    println!("Saved. Run `estimate-client config {path_to_file}` to use these answers again.");
+    Ok(())
+}
+
+/// Prints the taxes for the answers given so far
+fn show_running_estimate(tax_info: &TaxInfo) {
+    match client::calculate_taxes(tax_info.clone()) {
+        Ok(results) => println!(
+            "Running estimate: federal tax {}, state tax {}, net income {}",
+            results.federal_tax, results.state_tax, results.net_income
+        ),
+        Err(err) => println!("Running estimate unavailable: {err}"),
+    }
+}
+
+fn prompt_error(err: dialoguer::Error) -> EstimaterErrors {
+    EstimaterErrors::UserError(format!("Could not read the answer: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_validate_amount() {
+        assert!(validate_amount(0.0, 100.0).is_ok());
+        assert!(validate_amount(100.0, 100.0).is_ok());
+        assert!(validate_amount(100.01, 100.0).is_err());
+        assert!(validate_amount(-1.0, 100.0).is_err());
+        assert!(validate_amount(f64::NAN, f64::MAX).is_err());
+        assert!(validate_amount(f64::INFINITY, f64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_validate_rate() {
+        assert!(validate_rate(0.0).is_ok());
+        assert!(validate_rate(9.3).is_ok());
+        assert!(validate_rate(100.0).is_ok());
+        assert!(validate_rate(-0.5).is_err());
+        assert!(validate_rate(100.5).is_err());
+    }
+
+    #[test]
+    fn test_validate_date() {
+        let start = NaiveDate::from_ymd_opt(2023, 3, 1).unwrap();
+        assert!(validate_date(start, None).is_ok());
+        assert!(validate_date(start, Some(start)).is_ok());
+        assert!(validate_date(start.pred_opt().unwrap(), Some(start)).is_err());
+    }
+
+    #[test]
+    fn test_each_deduction_prompt_has_its_own_type() {
+        for (index, (deduction_type, _)) in DEDUCTION_PROMPTS.iter().enumerate() {
+            assert!(
+                !DEDUCTION_PROMPTS[index + 1..]
+                    .iter()
+                    .any(|(other_type, _)| other_type == deduction_type),
+                "{deduction_type} is asked for twice"
+            );
+        }
+        // A 403(b) is no longer saved as a 401(k)
+        assert!(DEDUCTION_PROMPTS
+            .iter()
+            .any(|(deduction_type, _)| *deduction_type == DeductionType::Traditional403b));
+        // Other pre-tax deductions are only settable from a config file
+        assert!(!DEDUCTION_PROMPTS
+            .iter()
+            .any(|(deduction_type, _)| *deduction_type == DeductionType::Other));
+    }
+}
@@ -2,6 +2,8 @@
 
 mod cli;
 pub(crate) mod client;
+mod interactive;
+mod tui;
 
 // Expose cli as the main executable
 pub fn main() {
@@ -0,0 +1,526 @@
+//! Full screen terminal UI where every change to the inputs immediately updates the estimate
+//! and a chart of the income taxed in each federal bracket.
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, Block, Paragraph},
+    DefaultTerminal, Frame,
+};
+use std::{io, path::PathBuf};
+
+use crate::client;
+
+use estimate_common::{
+    common::{BracketBreakdown, FilingStatus, TaxInfo, TaxResults},
+    deductions::{DeductionType, PreTaxDeduction},
+    errors::{EstimaterErrors, EstimaterResult},
+    file_format::write_file,
+};
+
+/// How many steps PageUp / PageDown adjust a field by
+const LARGE_STEP: f64 = 10.0;
+
+/// The inputs that can be changed from the UI
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Salary,
+    Bonus,
+    /// The yearly amount of one type of pre-tax deduction. The untyped deductions are of type
+    /// other.
+    Deduction(DeductionType),
+    StateTaxRate,
+    FilingStatus,
+}
+
+const FIELDS: [Field; 12] = [
+    Field::Salary,
+    Field::Bonus,
+    Field::Deduction(DeductionType::Traditional401k),
+    Field::Deduction(DeductionType::Traditional403b),
+    Field::Deduction(DeductionType::Governmental457b),
+    Field::Deduction(DeductionType::Hsa),
+    Field::Deduction(DeductionType::HealthPremiums),
+    Field::Deduction(DeductionType::CommuterBenefits),
+    Field::Deduction(DeductionType::DependentCareFsa),
+    Field::Deduction(DeductionType::Other),
+    Field::StateTaxRate,
+    Field::FilingStatus,
+];
+
+const FILING_STATUSES: [FilingStatus; 3] = [
+    FilingStatus::Single,
+    FilingStatus::MarriedFilingJointly,
+    FilingStatus::MarriedFilingSeparately,
+];
+
+impl Field {
+    fn label(&self) -> &'static str {
+        match self {
+            Field::Salary => "Salary / wages",
+            Field::Bonus => "Bonus",
+            Field::Deduction(deduction_type) => match deduction_type {
+                DeductionType::Traditional401k => "401(k) contributions",
+                DeductionType::Traditional403b => "403(b) contributions",
+                DeductionType::Governmental457b => "457(b) contributions",
+                DeductionType::Hsa => "HSA contributions",
+                DeductionType::HealthPremiums => "Health premiums",
+                DeductionType::CommuterBenefits => "Commuter benefits",
+                DeductionType::DependentCareFsa => "Dependent care FSA",
+                DeductionType::Other => "Other pre-tax deductions",
+            },
+            Field::StateTaxRate => "State tax rate %",
+            Field::FilingStatus => "Filing status",
+        }
+    }
+
+    /// How much a single left / right press changes the field by
+    fn step(&self) -> f64 {
+        match self {
+            Field::Salary | Field::Bonus => 1000.0,
+            Field::Deduction(_) => 500.0,
+            Field::StateTaxRate => 0.25,
+            Field::FilingStatus => 1.0,
+        }
+    }
+}
+
+/// The taxes for the current inputs, along with the federal bracket breakdown
+type Estimate = EstimaterResult<(TaxResults, Vec<BracketBreakdown>)>;
+
+/// The state of the UI
+struct App {
+    tax_info: TaxInfo,
+    path_to_file: PathBuf,
+    selected: usize,
+    /// The text typed so far while entering a value for the selected field
+    editing: Option<String>,
+    /// A message about the last action, i.e. saving
+    status: String,
+    estimate: Estimate,
+    should_quit: bool,
+}
+
+/// Runs the terminal UI until the user quits.
+///
+/// # Params
+/// * `tax_info` - The inputs to start with
+/// * `path_to_file` - Where the inputs are saved to
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when the terminal could not be used
+/// * `Ok(TaxResults)`: The taxes for the inputs when the user quit
+pub(crate) fn run_tui(tax_info: TaxInfo, path_to_file: PathBuf) -> EstimaterResult<TaxResults> {
+    let mut app = App::new(tax_info, path_to_file);
+    let terminal = ratatui::try_init().map_err(terminal_error)?;
+    let run_res = app.run(terminal);
+    ratatui::restore();
+    run_res.map_err(terminal_error)?;
+    client::calculate_taxes(app.tax_info)
+}
+
+impl App {
+    fn new(tax_info: TaxInfo, path_to_file: PathBuf) -> Self {
+        let mut app = Self {
+            tax_info,
+            path_to_file,
+            selected: 0,
+            editing: None,
+            status: String::new(),
+            estimate: Err(EstimaterErrors::ServerError("Not calculated".to_string())),
+            should_quit: false,
+        };
+        app.recalculate();
+        app
+    }
+
+    fn run(&mut self, mut terminal: DefaultTerminal) -> io::Result<()> {
+        while !self.should_quit {
+            terminal.draw(|frame| self.render(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn recalculate(&mut self) {
+        let tax_info = self.tax_info.clone();
+        self.estimate = client::calculate_taxes(tax_info.clone()).and_then(|results| {
+            client::calculate_federal_bracket_breakdown(tax_info)
+                .map(|breakdown| (results, breakdown))
+        });
+    }
+
+    fn selected_field(&self) -> Field {
+        FIELDS[self.selected]
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if let Some(text) = self.editing.as_mut() {
+            match key.code {
+                KeyCode::Char(character) if character.is_ascii_digit() || character == '.' => {
+                    text.push(character)
+                }
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Enter => {
+                    let text = self.editing.take().unwrap_or_default();
+                    self.status = match self.set_value(self.selected_field(), &text) {
+                        Ok(()) => String::new(),
+                        Err(err) => err,
+                    };
+                }
+                KeyCode::Esc => self.editing = None,
+                _ => {}
+            }
+            return;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
+            KeyCode::Up => self.selected = self.selected.checked_sub(1).unwrap_or(FIELDS.len() - 1),
+            KeyCode::Down | KeyCode::Tab => self.selected = (self.selected + 1) % FIELDS.len(),
+            KeyCode::Left => self.adjust(self.selected_field(), -1.0),
+            KeyCode::Right => self.adjust(self.selected_field(), 1.0),
+            KeyCode::PageDown => self.adjust(self.selected_field(), -LARGE_STEP),
+            KeyCode::PageUp => self.adjust(self.selected_field(), LARGE_STEP),
+            KeyCode::Enter if self.selected_field() != Field::FilingStatus => {
+                self.editing = Some(String::new())
+            }
+            KeyCode::Char('s') => self.save(),
+            _ => {}
+        }
+    }
+
+    /// Moves a field by a number of its steps, keeping it within its bounds
+    fn adjust(&mut self, field: Field, steps: f64) {
+        let change = steps * field.step();
+        let tax_info = &mut self.tax_info;
+        match field {
+            Field::Salary => {
+                tax_info.gross_yearly_income =
+                    (tax_info.gross_yearly_income as f64 + change).max(0.0) as u64
+            }
+            Field::Bonus => tax_info.bonus_income = (tax_info.bonus_income + change).max(0.0),
+            Field::Deduction(deduction_type) => {
+                let amount = (deduction_amount(tax_info, deduction_type) + change)
+                    .clamp(0.0, deduction_room(tax_info, deduction_type));
+                set_deduction_amount(tax_info, deduction_type, amount)
+            }
+            Field::StateTaxRate => {
+                tax_info.state_tax_rate_percent =
+                    (tax_info.state_tax_rate_percent + change).clamp(0.0, 100.0)
+            }
+            Field::FilingStatus => {
+                let current = FILING_STATUSES
+                    .iter()
+                    .position(|status| *status == tax_info.filing_status)
+                    .unwrap_or(0);
+                let next = (current as i64 + steps.signum() as i64)
+                    .rem_euclid(FILING_STATUSES.len() as i64);
+                tax_info.filing_status = FILING_STATUSES[next as usize];
+            }
+        }
+        self.recalculate();
+    }
+
+    /// Sets a field to a typed value
+    fn set_value(&mut self, field: Field, text: &str) -> Result<(), String> {
+        let value: f64 = text
+            .parse()
+            .map_err(|_| format!("{text:?} is not a number"))?;
+        let tax_info = &mut self.tax_info;
+        match field {
+            Field::Salary => tax_info.gross_yearly_income = value.round() as u64,
+            Field::Bonus => tax_info.bonus_income = value,
+            Field::Deduction(deduction_type)
+                if value > deduction_room(tax_info, deduction_type) =>
+            {
+                return Err("Pre-tax deductions cannot be more than the wages".to_string())
+            }
+            Field::Deduction(deduction_type) => {
+                set_deduction_amount(tax_info, deduction_type, value)
+            }
+            Field::StateTaxRate if value > 100.0 => {
+                return Err("The state tax rate cannot be more than 100%".to_string())
+            }
+            Field::StateTaxRate => tax_info.state_tax_rate_percent = value,
+            Field::FilingStatus => {}
+        }
+        self.recalculate();
+        Ok(())
+    }
+
+    fn save(&mut self) {
+        self.status = match write_file(&self.path_to_file, &self.tax_info) {
+            Ok(()) => format!("Saved to {}", self.path_to_file.display()),
+            Err(err) => format!("Could not save: {err}"),
+        };
+    }
+
+    /// The selected field's value as displayed
+    fn display_value(&self, field: Field) -> String {
+        if field == self.selected_field() {
+            if let Some(text) = &self.editing {
+                return format!("{text}_");
+            }
+        }
+        match field {
+            Field::Salary => self.tax_info.gross_yearly_income.to_string(),
+            Field::Bonus => self.tax_info.bonus_income.to_string(),
+            Field::Deduction(deduction_type) => {
+                deduction_amount(&self.tax_info, deduction_type).to_string()
+            }
+            Field::StateTaxRate => self.tax_info.state_tax_rate_percent.to_string(),
+            Field::FilingStatus => self.tax_info.filing_status.to_string(),
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [top, chart, footer] = Layout::vertical([
+            Constraint::Length(FIELDS.len() as u16 + 2),
+            Constraint::Min(8),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [inputs, estimate] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(top);
+
+        self.render_inputs(frame, inputs);
+        self.render_estimate(frame, estimate);
+        self.render_chart(frame, chart);
+
+        let help =
+            "↑/↓ select  ←/→ adjust  PgUp/PgDn adjust x10  Enter type a value  s save  q quit";
+        frame.render_widget(Paragraph::new(format!("{help}  {}", self.status)), footer);
+    }
+
+    fn render_inputs(&self, frame: &mut Frame, area: Rect) {
+        let lines: Vec<Line> = FIELDS
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let line = Line::from(format!(
+                    "{} {:<36}{}",
+                    if index == self.selected { ">" } else { " " },
+                    field.label(),
+                    self.display_value(*field)
+                ));
+                if index == self.selected {
+                    line.style(Style::default().add_modifier(Modifier::BOLD))
+                } else {
+                    line
+                }
+            })
+            .collect();
+        let title = format!(" Inputs ({}) ", self.path_to_file.display());
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(title)),
+            area,
+        );
+    }
+
+    fn render_estimate(&self, frame: &mut Frame, area: Rect) {
+        let lines: Vec<Line> = match &self.estimate {
+            Err(err) => vec![Line::from(err.to_string()).style(Style::default().fg(Color::Red))],
+            Ok((results, breakdown)) => {
+                let income = results.net_income + results.total_tax();
+                let effective_rate = if income > 0.0 {
+                    results.total_tax() / income * 100.0
+                } else {
+                    0.0
+                };
+                let marginal_rate = breakdown
+                    .iter()
+                    .rev()
+                    .find(|bracket| bracket.taxed_income > 0.0)
+                    .map_or(0.0, |bracket| bracket.tax_rate * 100.0);
+                let mut lines = vec![
+                    Line::from(format!("Income:          {:>12.2}", income)),
+                    Line::from(format!("Federal tax:     {:>12.2}", results.federal_tax)),
+                    Line::from(format!("State tax:       {:>12.2}", results.state_tax)),
+                    Line::from(format!("Total tax:       {:>12.2}", results.total_tax())),
+                    Line::from(format!("Net income:      {:>12.2}", results.net_income)),
+                    Line::from(format!(
+                        "Effective rate:  {:>11.2}%   Federal marginal rate: {}%",
+                        effective_rate, marginal_rate
+                    )),
+                ];
+                // Without withholding there is no refund, only the tax already shown
+                if results.has_federal_withholding() {
+                    lines.push(Line::from(format!(
+                        "Federal refund:  {:>12.2}",
+                        results.federal_refund()
+                    )));
+                }
+                if results.has_state_withholding() {
+                    lines.push(Line::from(format!(
+                        "State refund:    {:>12.2}",
+                        results.state_refund()
+                    )));
+                }
+                lines
+            }
+        };
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Estimate ")),
+            area,
+        );
+    }
+
+    fn render_chart(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Taxable income in each federal bracket ");
+        let Ok((_, breakdown)) = &self.estimate else {
+            frame.render_widget(block, area);
+            return;
+        };
+
+        let bars: Vec<Bar> = breakdown
+            .iter()
+            .map(|bracket| {
+                Bar::default()
+                    .value(bracket.taxed_income.round() as u64)
+                    .text_value(format!("{:.0}", bracket.taxed_income))
+                    .label(Line::from(format!("{}%", bracket.tax_rate * 100.0)))
+            })
+            .collect();
+        let inner_width = area.width.saturating_sub(2);
+        let bar_count = breakdown.len().max(1) as u16;
+        let bar_width = (inner_width / bar_count).saturating_sub(1).max(1);
+        frame.render_widget(
+            BarChart::default()
+                .block(block)
+                .data(BarGroup::default().bars(&bars))
+                .bar_width(bar_width)
+                .bar_gap(1)
+                .bar_style(Style::default().fg(Color::Cyan))
+                .value_style(Style::default().fg(Color::Black).bg(Color::Cyan)),
+            area,
+        );
+    }
+}
+
+/// The yearly amount of one type of pre-tax deduction
+fn deduction_amount(tax_info: &TaxInfo, deduction_type: DeductionType) -> f64 {
+    tax_info
+        .pre_tax_deductions()
+        .iter()
+        .filter(|deduction| deduction.deduction_type == deduction_type)
+        .map(|deduction| deduction.amount)
+        .sum()
+}
+
+/// The most one type of pre-tax deduction can be, so that all of them fit within the wages
+fn deduction_room(tax_info: &TaxInfo, deduction_type: DeductionType) -> f64 {
+    let other_deductions =
+        tax_info.total_pre_tax_deductions() - deduction_amount(tax_info, deduction_type);
+    (tax_info.total_wages() - other_deductions).max(0.0)
+}
+
+/// Replaces the deductions of one type with a single deduction of the amount, which keeps
+/// whether the state taxes them. The untyped deductions are of type other, so they are replaced
+/// along with it.
+fn set_deduction_amount(tax_info: &mut TaxInfo, deduction_type: DeductionType, amount: f64) {
+    if deduction_type == DeductionType::Other {
+        tax_info.pre_tax_deducations = 0.0;
+    }
+    let state_taxed = tax_info
+        .deductions
+        .iter()
+        .any(|deduction| deduction.deduction_type == deduction_type && deduction.state_taxed);
+    tax_info
+        .deductions
+        .retain(|deduction| deduction.deduction_type != deduction_type);
+    if amount > 0.0 {
+        tax_info.deductions.push(PreTaxDeduction {
+            deduction_type,
+            amount,
+            state_taxed,
+        });
+    }
+}
+
+fn terminal_error(err: io::Error) -> EstimaterErrors {
+    EstimaterErrors::UserError(format!("Could not use the terminal: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn help_make_info() -> TaxInfo {
+        TaxInfo {
+            gross_yearly_income: 50000,
+            deductions: vec![
+                PreTaxDeduction {
+                    deduction_type: DeductionType::Traditional401k,
+                    amount: 10000.0,
+                    state_taxed: false,
+                },
+                PreTaxDeduction {
+                    deduction_type: DeductionType::Other,
+                    amount: 1000.0,
+                    state_taxed: true,
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_set_deduction_amount_keeps_state_taxed() {
+        let mut tax_info = help_make_info();
+        set_deduction_amount(&mut tax_info, DeductionType::Other, 2000.0);
+        assert_eq!(tax_info.deductions.len(), 2);
+        assert_eq!(tax_info.deductions[0].amount, 10000.0);
+        assert_eq!(tax_info.deductions[1].deduction_type, DeductionType::Other);
+        assert_eq!(tax_info.deductions[1].amount, 2000.0);
+        assert!(tax_info.deductions[1].state_taxed);
+        assert_eq!(tax_info.pre_tax_deducations, 0.0);
+
+        set_deduction_amount(&mut tax_info, DeductionType::Traditional401k, 0.0);
+        assert_eq!(tax_info.deductions.len(), 1);
+        assert_eq!(
+            deduction_amount(&tax_info, DeductionType::Traditional401k),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_set_deduction_amount_replaces_untyped() {
+        let mut tax_info = help_make_info();
+        tax_info.pre_tax_deducations = 500.0;
+        assert_eq!(deduction_amount(&tax_info, DeductionType::Other), 1500.0);
+
+        set_deduction_amount(&mut tax_info, DeductionType::Other, 1200.0);
+        assert_eq!(deduction_amount(&tax_info, DeductionType::Other), 1200.0);
+        assert_eq!(tax_info.pre_tax_deducations, 0.0);
+
+        set_deduction_amount(&mut tax_info, DeductionType::Hsa, 300.0);
+        assert_eq!(tax_info.deductions.len(), 3);
+        assert!(!tax_info.deductions[2].state_taxed);
+    }
+
+    #[test]
+    fn test_deduction_room() {
+        let mut tax_info = help_make_info();
+        tax_info.pre_tax_deducations = 500.0;
+        // The wages less every other deduction, including the untyped ones
+        assert_eq!(
+            deduction_room(&tax_info, DeductionType::Traditional401k),
+            48500.0
+        );
+        assert_eq!(deduction_room(&tax_info, DeductionType::Other), 40000.0);
+        assert_eq!(deduction_room(&tax_info, DeductionType::Hsa), 38500.0);
+
+        tax_info.gross_yearly_income = 5000;
+        assert_eq!(deduction_room(&tax_info, DeductionType::Hsa), 0.0);
+    }
+}
@@ -0,0 +1,123 @@
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// One tier of an employer's 401(k) match formula, i.e. 100% of the first 4% of pay. Tiers
+/// apply in order, each to the next slice of pay deferred.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub struct MatchTier {
+    /// The % of the deferrals within the tier the employer matches
+    pub match_percent: f64,
+    /// The width of the tier, as a % of pay
+    pub of_pay_percent: f64,
+}
+
+/// Parses a match tier given as `MATCH:OF_PAY`, i.e. `100:4` for 100% up to 4% of pay.
+impl FromStr for MatchTier {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (match_percent, of_pay_percent) = value
+            .split_once(':')
+            .ok_or_else(|| format!("Expected MATCH:OF_PAY, got {value:?}"))?;
+        let parse_percent = |percent: &str| -> Result<f64, String> {
+            percent
+                .trim()
+                .parse()
+                .map_err(|_| format!("{percent:?} is not a %"))
+        };
+        Ok(Self {
+            match_percent: parse_percent(match_percent)?,
+            of_pay_percent: parse_percent(of_pay_percent)?,
+        })
+    }
+}
+
+/// A benefit the employer pays for besides the match and HSA, i.e. insurance premiums or a
+/// wellness stipend
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OtherBenefit {
+    pub name: String,
+    /// The yearly value of the benefit
+    pub value: f64,
+}
+
+/// What the employer pays on top of wages
+#[derive(Args, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct EmployerBenefits {
+    #[clap(long = "employer-match")]
+    /// A tier of the 401(k) match as MATCH:OF_PAY, repeated for each tier. i.e.
+    /// `--employer-match 100:4 --employer-match 50:2` for 100% up to 4% of pay and 50% of the
+    /// next 2%.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub match_tiers: Vec<MatchTier>,
+    #[clap(long = "employer-hsa", default_value_t = 0.0)]
+    /// Yearly employer contributions to an HSA
+    #[serde(default)]
+    pub hsa_contribution: f64,
+    #[clap(skip)]
+    /// Any other benefits the employer pays for. Only settable from a config file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub other_benefits: Vec<OtherBenefit>,
+}
+
+impl EmployerBenefits {
+    /// Whether no benefits were given
+    pub fn is_unset(&self) -> bool {
+        self.match_tiers.is_empty()
+            && self.hsa_contribution == 0.0
+            && self.other_benefits.is_empty()
+    }
+
+    /// The yearly value of the other benefits
+    pub fn other_benefits_value(&self) -> f64 {
+        self.other_benefits
+            .iter()
+            .fold(0.0, |total, benefit| total + benefit.value)
+    }
+}
+
+/// Struct representing everything paid by the employer, before and after taxes
+pub struct CompensationResults {
+    /// All wages, regular and supplemental
+    pub wages: f64,
+    /// The employer's 401(k) match on the elective deferrals
+    pub employer_match: f64,
+    /// The employer's HSA contributions
+    pub employer_hsa: f64,
+    /// The value of the other benefits
+    pub other_benefits: f64,
+    /// The net income after taxes
+    pub net_income: f64,
+}
+
+impl CompensationResults {
+    /// Everything the employer pays for, none of which is taxed as wages
+    pub fn total_benefits(&self) -> f64 {
+        self.employer_match + self.employer_hsa + self.other_benefits
+    }
+
+    /// Wages and benefits together, before taxes
+    pub fn total_compensation(&self) -> f64 {
+        self.wages + self.total_benefits()
+    }
+
+    /// The net income plus the benefits
+    pub fn after_tax_compensation(&self) -> f64 {
+        self.net_income + self.total_benefits()
+    }
+}
+
+impl fmt::Display for CompensationResults {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Employer 401(k) Match: {}", self.employer_match)?;
+        writeln!(f, "Employer HSA Contributions: {}", self.employer_hsa)?;
+        writeln!(f, "Other Benefits: {}", self.other_benefits)?;
+        writeln!(f, "Total Compensation: {}", self.total_compensation())?;
+        write!(
+            f,
+            "After-Tax Compensation: {}",
+            self.after_tax_compensation()
+        )
+    }
+}
@@ -1,31 +1,300 @@
-use clap::Args;
-use serde::Deserialize;
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Args, Clone, Debug, Deserialize)]
+use crate::{
+    benefits::{CompensationResults, EmployerBenefits},
+    deductions::{DeductionType, PreTaxDeduction},
+    employment::{Employment, EmploymentResults},
+    equity::{EquityDispositionResults, EsppSale, IsoExercise, RsuResults, RsuVest},
+    utils::round_to_hundredths,
+};
+
+/// The status a return is filed under. Determines which federal brackets apply.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FilingStatus {
+    #[default]
+    Single,
+    MarriedFilingJointly,
+    MarriedFilingSeparately,
+}
+
+impl fmt::Display for FilingStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self {
+            FilingStatus::Single => "single",
+            FilingStatus::MarriedFilingJointly => "married filing jointly",
+            FilingStatus::MarriedFilingSeparately => "married filing separately",
+        };
+        write!(f, "{status}")
+    }
+}
+
+/// Income paid by the hour, converted to yearly wages
+#[derive(Args, Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct HourlyWage {
+    #[clap(long = "hourly-rate", default_value_t = 0.0)]
+    /// Pay per regular hour. 0 when not paid by the hour.
+    pub hourly_rate: f64,
+    #[clap(long = "hours-per-week", default_value_t = 40.0)]
+    /// Regular hours worked in a week
+    pub hours_per_week: f64,
+    #[clap(long = "weeks-per-year", default_value_t = 52.0)]
+    /// Weeks worked in a year
+    pub weeks_per_year: f64,
+    #[clap(long = "overtime-hours", default_value_t = 0.0)]
+    /// Overtime hours worked in a week, on top of the regular hours
+    pub overtime_hours_per_week: f64,
+    #[clap(long = "overtime-multiplier", default_value_t = 1.5)]
+    /// Multiple of the hourly rate paid for overtime hours
+    pub overtime_multiplier: f64,
+}
+
+impl Default for HourlyWage {
+    fn default() -> Self {
+        Self {
+            hourly_rate: 0.0,
+            hours_per_week: 40.0,
+            weeks_per_year: 52.0,
+            overtime_hours_per_week: 0.0,
+            overtime_multiplier: 1.5,
+        }
+    }
+}
+
+impl HourlyWage {
+    /// Whether no hourly income was given
+    pub fn is_unset(&self) -> bool {
+        self.hourly_rate == 0.0
+    }
+
+    /// The hours worked in a year, regular and overtime
+    pub fn yearly_hours(&self) -> f64 {
+        (self.hours_per_week + self.overtime_hours_per_week) * self.weeks_per_year
+    }
+
+    /// The wages paid over a year, regular and overtime
+    pub fn yearly_wages(&self) -> f64 {
+        let weekly_pay = self.hourly_rate * self.hours_per_week
+            + self.hourly_rate * self.overtime_multiplier * self.overtime_hours_per_week;
+        weekly_pay * self.weeks_per_year
+    }
+}
+
+#[derive(Args, Clone, Debug, Default, Deserialize, Serialize)]
 pub struct TaxInfo {
-    #[clap(long = "gross")]
+    #[clap(long = "gross", default_value_t = 0)]
+    /// Yearly salary. May be 0 when income is hourly or listed under the employments.
+    #[serde(default)]
     pub gross_yearly_income: u64,
-    #[clap(long = "federal")]
-    /// Federal tax as a %
+    #[clap(long = "federal", default_value_t = 0.0)]
+    /// Federal tax as a %. Unused, federal tax is calculated from the brackets.
+    #[serde(default)]
     pub federal_tax_rate_percent: f64,
     #[clap(long = "state")]
     /// State tax as a %
     pub state_tax_rate_percent: f64,
-    #[clap(short, long = "pre-tax-deductions")]
+    #[clap(short, long = "pre-tax-deductions", default_value_t = 0.0)]
+    /// Untyped yearly amounts taken out of pay before income tax. Treated as a deduction of type
+    /// other: excluded from federal and state income tax, still subject to Social Security and
+    /// Medicare, and not a retirement deferral. Prefer the typed `--deduction`.
+    #[serde(default)]
     pub pre_tax_deducations: f64,
+    #[clap(long = "deduction")]
+    /// Typed yearly pre-tax deductions as TYPE=AMOUNT, i.e. 401k=22500 or hsa=3850. Types are
+    /// 401k, 403b, 457b, hsa, health_premiums, commuter_benefits, dependent_care_fsa and other.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deductions: Vec<PreTaxDeduction>,
+    #[clap(long = "roth-deferrals", default_value_t = 0.0)]
+    /// Yearly Roth 401(k) / 403(b) deferrals. Taken out of pay after income tax, but matched
+    /// and limited like pre-tax deferrals.
+    #[serde(default)]
+    pub roth_deferrals: f64,
+    #[clap(long = "after-tax-contributions", default_value_t = 0.0)]
+    /// Yearly after-tax (non-Roth) 401(k) contributions, i.e. for a mega-backdoor Roth. Taken
+    /// out of pay after income tax, and not matched.
+    #[serde(default)]
+    pub after_tax_contributions: f64,
+    #[clap(long = "bonus", default_value_t = 0.0)]
+    /// Bonuses and other supplemental wages paid on top of the gross yearly income
+    #[serde(default)]
+    pub bonus_income: f64,
+    #[clap(flatten)]
+    /// Hourly income, added to the gross yearly income
+    #[serde(default, skip_serializing_if = "HourlyWage::is_unset")]
+    pub hourly: HourlyWage,
+    #[clap(skip)]
+    /// Restricted stock units vesting over the year. Only settable from a config file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rsu_vests: Vec<RsuVest>,
+    #[clap(skip)]
+    /// Shares bought through an ESPP and sold this year. Only settable from a config file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub espp_sales: Vec<EsppSale>,
+    #[clap(skip)]
+    /// Incentive stock options exercised or sold this year. An unsold exercise is taken to be
+    /// from this year, and a sold one from an earlier year or this one. Only settable from a
+    /// config file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub iso_exercises: Vec<IsoExercise>,
+    #[clap(skip)]
+    /// Jobs held over the year, each with its own wages and withholding. Their wages are added
+    /// to the gross yearly income. Their year picks the Social Security wage base, but not the
+    /// federal brackets, which are always those of the bracket file. Only settable from a config
+    /// file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub employments: Vec<Employment>,
+    #[clap(flatten)]
+    /// Benefits the employer pays on top of wages, i.e. the 401(k) match
+    #[serde(default, skip_serializing_if = "EmployerBenefits::is_unset")]
+    pub benefits: EmployerBenefits,
+    #[clap(long = "filing-status", value_enum, default_value_t = FilingStatus::Single)]
+    /// Filing status of the return. Defaults to single when not provided.
+    #[serde(default)]
+    pub filing_status: FilingStatus,
+    #[clap(long = "federal-withholding-ytd", default_value_t = 0.0)]
+    /// Federal tax withheld so far this year from regular pay. The flat rate withholding on
+    /// supplemental wages is estimated separately, so is not part of it.
+    #[serde(default)]
+    pub federal_withholding_ytd: f64,
+    #[clap(long = "federal-withholding-projected", default_value_t = 0.0)]
+    /// Federal tax expected to be withheld from regular pay over the rest of the year
+    #[serde(default)]
+    pub federal_withholding_projected: f64,
+    #[clap(long = "state-withholding-ytd", default_value_t = 0.0)]
+    /// State tax withheld so far this year
+    #[serde(default)]
+    pub state_withholding_ytd: f64,
+    #[clap(long = "state-withholding-projected", default_value_t = 0.0)]
+    /// State tax expected to be withheld over the rest of the year
+    #[serde(default)]
+    pub state_withholding_projected: f64,
+}
+
+impl TaxInfo {
+    /// Wages paid separately from regular pay, which are withheld at a flat rate
+    pub fn supplemental_wages(&self) -> f64 {
+        self.bonus_income + self.rsu_income()
+    }
+
+    /// Wages recognized by all RSU vests
+    pub fn rsu_income(&self) -> f64 {
+        self.rsu_vests.iter().map(RsuVest::income).sum()
+    }
+
+    /// Wages paid by all employments
+    pub fn employment_wages(&self) -> f64 {
+        self.employments
+            .iter()
+            .map(|employment| employment.wages)
+            .sum()
+    }
+
+    /// Every pre-tax deduction, with the untyped pre-tax deductions as a deduction of type
+    /// other
+    pub fn pre_tax_deductions(&self) -> Vec<PreTaxDeduction> {
+        let mut deductions = self.deductions.clone();
+        if self.pre_tax_deducations != 0.0 {
+            deductions.push(PreTaxDeduction::new(
+                DeductionType::Other,
+                self.pre_tax_deducations,
+            ));
+        }
+        deductions
+    }
+
+    /// The sum of the pre-tax deductions matching a filter. 0 (rather than -0) when none match.
+    fn sum_pre_tax_deductions(&self, filter: impl Fn(&PreTaxDeduction) -> bool) -> f64 {
+        self.pre_tax_deductions()
+            .iter()
+            .filter(|deduction| filter(deduction))
+            .fold(0.0, |total, deduction| total + deduction.amount)
+    }
+
+    /// Every pre-tax deduction. All of them are excluded from federal income tax.
+    pub fn total_pre_tax_deductions(&self) -> f64 {
+        self.sum_pre_tax_deductions(|_| true)
+    }
+
+    /// The pre-tax 401(k) and 403(b) deferrals, without the Roth deferrals
+    pub fn pre_tax_elective_deferrals(&self) -> f64 {
+        self.sum_pre_tax_deductions(|deduction| deduction.deduction_type.is_elective_deferral())
+    }
+
+    /// The pre-tax and Roth elective deferrals to a 401(k) or 403(b) plan
+    pub fn elective_deferrals(&self) -> f64 {
+        self.pre_tax_elective_deferrals() + self.roth_deferrals
+    }
+
+    /// The pre-tax deductions the state excludes from income tax
+    pub fn state_pre_tax_deductions(&self) -> f64 {
+        self.sum_pre_tax_deductions(|deduction| !deduction.state_taxed)
+    }
+
+    /// The pre-tax deductions also excluded from Social Security and Medicare wages
+    pub fn fica_exempt_deductions(&self) -> f64 {
+        self.sum_pre_tax_deductions(|deduction| !deduction.deduction_type.is_fica_taxed())
+    }
+
+    /// Wages paid in regular paychecks, from the salary and hourly income
+    pub fn regular_wages(&self) -> f64 {
+        self.gross_yearly_income as f64 + self.hourly.yearly_wages()
+    }
+
+    /// All wages, regular and supplemental
+    pub fn total_wages(&self) -> f64 {
+        self.regular_wages() + self.supplemental_wages() + self.employment_wages()
+    }
+
+    /// Federal tax withheld over the entire year, including by all employments
+    pub fn total_federal_withholding(&self) -> f64 {
+        self.federal_withholding_ytd
+            + self.federal_withholding_projected
+            + self
+                .employments
+                .iter()
+                .map(|employment| employment.federal_withholding)
+                .sum::<f64>()
+    }
+
+    /// State tax withheld over the entire year, including by all employments
+    pub fn total_state_withholding(&self) -> f64 {
+        self.state_withholding_ytd
+            + self.state_withholding_projected
+            + self
+                .employments
+                .iter()
+                .map(|employment| employment.state_withholding)
+                .sum::<f64>()
+    }
 }
 
 impl fmt::Display for TaxInfo {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Tax info: gross income: {} (deducations = {}), state tax: {}, federal tax: {}",
+            "Tax info: gross income: {} (deducations = {}, bonus = {}), state tax: {}, federal tax: {}, filing status: {}",
             self.gross_yearly_income,
-            self.pre_tax_deducations,
+            self.total_pre_tax_deductions(),
+            self.bonus_income,
             self.state_tax_rate_percent,
-            self.federal_tax_rate_percent
-        )
+            self.federal_tax_rate_percent,
+            self.filing_status
+        )?;
+        if !self.hourly.is_unset() {
+            write!(
+                f,
+                ", hourly: {} for {} hours (+ {} overtime at {}x) a week, {} weeks a year",
+                self.hourly.hourly_rate,
+                self.hourly.hours_per_week,
+                self.hourly.overtime_hours_per_week,
+                self.hourly.overtime_multiplier,
+                self.hourly.weeks_per_year
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -37,16 +306,99 @@ pub struct TaxResults {
     pub state_tax: f64,
     /// Amount leftover after taxes + pre-tax removals
     pub net_income: f64,
+    /// Amount withheld for federal taxes over the year, as given. 0 when none was given.
+    pub federal_withholding: f64,
+    /// Amount withheld for state taxes over the year, as given. 0 when none was given.
+    pub state_withholding: f64,
+    /// Withholding on bonuses compared to the tax they cause. Only set when there is a bonus.
+    /// RSU vests are compared on their own in `rsu_vests`.
+    pub supplemental_wages: Option<SupplementalWageResults>,
+    /// Withholding on RSU vests compared to the tax they cause. Only set when there are vests.
+    pub rsu_vests: Option<RsuResults>,
+    /// Income from ESPP and ISO sales. Only set when there are sales or exercises. Only the
+    /// ordinary income is taxed, the capital gains are left out of the estimate.
+    pub equity_dispositions: Option<EquityDispositionResults>,
+    /// Social Security and Medicare withheld by each employer. Only set when there are
+    /// employments.
+    pub employments: Option<EmploymentResults>,
+    /// Net income per hour worked. Only set when all income is hourly, as the net income of
+    /// other income can not be told apart from it.
+    pub after_tax_hourly_rate: Option<f64>,
+    /// Wages and employer benefits together. Only set when there are benefits.
+    pub compensation: Option<CompensationResults>,
 }
 
 impl TaxResults {
-    pub fn new(federal_tax: f64, state_tax: f64, net_income: f64,) -> Self {
+    pub fn new(
+        federal_tax: f64,
+        state_tax: f64,
+        net_income: f64,
+        federal_withholding: f64,
+        state_withholding: f64,
+    ) -> Self {
         Self {
             federal_tax,
             state_tax,
-            net_income
+            net_income,
+            federal_withholding,
+            state_withholding,
+            supplemental_wages: None,
+            rsu_vests: None,
+            equity_dispositions: None,
+            employments: None,
+            after_tax_hourly_rate: None,
+            compensation: None,
         }
     }
+
+    /// Whether any federal withholding was given. Without it there is no refund to expect.
+    pub fn has_federal_withholding(&self) -> bool {
+        self.federal_withholding != 0.0
+    }
+
+    /// Whether any state withholding was given. Without it there is no refund to expect.
+    pub fn has_state_withholding(&self) -> bool {
+        self.state_withholding != 0.0
+    }
+
+    /// The expected federal refund, including the withholding on bonuses and RSU vests and any
+    /// excess Social Security credit, to the cent. Negative when money is owed instead.
+    pub fn federal_refund(&self) -> f64 {
+        round_to_hundredths(
+            self.federal_withholding
+                + self.supplemental_withholding()
+                + self.excess_social_security()
+                - self.federal_tax,
+        )
+    }
+
+    /// Federal tax withheld from bonuses and RSU vests at the flat supplemental rates
+    pub fn supplemental_withholding(&self) -> f64 {
+        self.supplemental_wages
+            .as_ref()
+            .map_or(0.0, |supplemental_wages| supplemental_wages.withheld)
+            + self
+                .rsu_vests
+                .as_ref()
+                .map_or(0.0, |rsu_vests| rsu_vests.total_withheld())
+    }
+
+    /// Social Security tax withheld by more than one employer beyond the most owed
+    pub fn excess_social_security(&self) -> f64 {
+        self.employments
+            .as_ref()
+            .map_or(0.0, |employments| employments.excess_social_security)
+    }
+
+    /// The expected state refund, to the cent. Negative when money is owed instead.
+    pub fn state_refund(&self) -> f64 {
+        round_to_hundredths(self.state_withholding - self.state_tax)
+    }
+
+    /// The combined federal and state taxes
+    pub fn total_tax(&self) -> f64 {
+        self.federal_tax + self.state_tax
+    }
 }
 
 impl fmt::Display for TaxResults {
@@ -55,6 +407,173 @@ impl fmt::Display for TaxResults {
             f,
             "Net Income: {}\nState Taxes: {}\nFederal Taxes: {}",
             self.net_income, self.state_tax, self.federal_tax
+        )?;
+        if self.has_federal_withholding() {
+            writeln!(f)?;
+            write_refund(f, "Federal", self.federal_refund())?;
+        }
+        if self.has_state_withholding() {
+            writeln!(f)?;
+            write_refund(f, "State", self.state_refund())?;
+        }
+        if let Some(after_tax_hourly_rate) = self.after_tax_hourly_rate {
+            write!(f, "\nAfter-Tax Hourly Rate: {after_tax_hourly_rate}")?;
+        }
+        if let Some(supplemental_wages) = &self.supplemental_wages {
+            write!(f, "\n{supplemental_wages}")?;
+        }
+        if let Some(rsu_vests) = &self.rsu_vests {
+            write!(f, "\n{rsu_vests}")?;
+        }
+        if let Some(equity_dispositions) = &self.equity_dispositions {
+            write!(f, "\n{equity_dispositions}")?;
+        }
+        if let Some(employments) = &self.employments {
+            write!(f, "\n{employments}")?;
+        }
+        if let Some(compensation) = &self.compensation {
+            write!(f, "\n{compensation}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Struct comparing the federal withholding taken from supplemental wages (i.e. bonuses) to
+/// the federal tax they actually add.
+pub struct SupplementalWageResults {
+    /// Amount withheld from the supplemental wages at the flat supplemental rates
+    pub withheld: f64,
+    /// Amount of federal tax added by the supplemental wages on top of the regular wages
+    pub incremental_tax: f64,
+}
+
+impl SupplementalWageResults {
+    /// The amount withheld beyond the tax caused. Negative when too little is withheld.
+    pub fn withholding_gap(&self) -> f64 {
+        self.withheld - self.incremental_tax
+    }
+}
+
+impl fmt::Display for SupplementalWageResults {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Supplemental Wage Withholding: {}\nSupplemental Wage Federal Taxes: {}\n",
+            self.withheld, self.incremental_tax
+        )?;
+        write_refund(f, "Supplemental Wage", self.withholding_gap())
+    }
+}
+
+/// Writes a refund as either the amount refunded or the amount owed
+fn write_refund(f: &mut fmt::Formatter, jurisdiction: &str, refund: f64) -> fmt::Result {
+    if refund >= 0.0 {
+        write!(f, "{jurisdiction} Refund: {refund}")
+    } else {
+        write!(f, "{jurisdiction} Amount Owed: {}", -refund)
+    }
+}
+
+/// The income taxed, and the tax owed, within a single federal bracket
+#[derive(Clone, Debug, PartialEq)]
+pub struct BracketBreakdown {
+    /// The taxable income above which the bracket's rate applies
+    pub threshold: f64,
+    /// The rate applied within the bracket, as a fraction
+    pub tax_rate: f64,
+    /// The portion of the taxable income that falls within the bracket
+    pub taxed_income: f64,
+    /// The tax owed on `taxed_income`
+    pub tax: f64,
+}
+
+/// Struct representing the taxes a couple pays under each way they could file.
+pub struct MarriageAnalysis {
+    /// The first person's taxes when filing as single
+    pub first_single: TaxResults,
+    /// The second person's taxes when filing as single
+    pub second_single: TaxResults,
+    /// The couple's taxes when filing a single joint return
+    pub married_jointly: TaxResults,
+    /// The first person's taxes when married but filing separately
+    pub first_separately: TaxResults,
+    /// The second person's taxes when married but filing separately
+    pub second_separately: TaxResults,
+}
+
+impl MarriageAnalysis {
+    /// Combined taxes of both people filing as single
+    pub fn total_tax_single(&self) -> f64 {
+        self.first_single.total_tax() + self.second_single.total_tax()
+    }
+
+    /// Combined taxes of the couple filing jointly
+    pub fn total_tax_jointly(&self) -> f64 {
+        self.married_jointly.total_tax()
+    }
+
+    /// Combined taxes of the couple filing separately
+    pub fn total_tax_separately(&self) -> f64 {
+        self.first_separately.total_tax() + self.second_separately.total_tax()
+    }
+
+    /// The extra taxes owed by filing jointly instead of as two single filers.
+    /// Positive is a marriage penalty, negative is a marriage bonus.
+    pub fn joint_marriage_penalty(&self) -> f64 {
+        self.total_tax_jointly() - self.total_tax_single()
+    }
+
+    /// The extra taxes owed by filing separately instead of as two single filers.
+    /// Positive is a marriage penalty, negative is a marriage bonus.
+    pub fn separate_marriage_penalty(&self) -> f64 {
+        self.total_tax_separately() - self.total_tax_single()
+    }
+}
+
+impl fmt::Display for MarriageAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Total Taxes (Single): {}", self.total_tax_single())?;
+        writeln!(
+            f,
+            "Total Taxes (Married Filing Jointly): {}",
+            self.total_tax_jointly()
+        )?;
+        writeln!(
+            f,
+            "Total Taxes (Married Filing Separately): {}",
+            self.total_tax_separately()
+        )?;
+        writeln!(
+            f,
+            "Marriage Penalty (Jointly): {}",
+            self.joint_marriage_penalty()
+        )?;
+        write!(
+            f,
+            "Marriage Penalty (Separately): {}",
+            self.separate_marriage_penalty()
         )
     }
 }
+
+/// Struct representing the gross income needed to take home a target net income.
+pub struct GrossIncomeSolution {
+    /// The net income asked for
+    pub target_net_income: f64,
+    /// The smallest whole dollar gross yearly income with a net income of at least the target
+    pub gross_yearly_income: u64,
+    /// The taxes at `gross_yearly_income`
+    pub results: TaxResults,
+}
+
+impl fmt::Display for GrossIncomeSolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Target Net Income: {}", self.target_net_income)?;
+        writeln!(
+            f,
+            "Gross Yearly Income Needed: {}",
+            self.gross_yearly_income
+        )?;
+        write!(f, "{}", self.results)
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// The kinds of deductions taken out of pay before tax. Each kind is excluded from federal
+/// income tax, but only some are also excluded from Social Security and Medicare (FICA).
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeductionType {
+    /// Traditional (not Roth) 401(k) deferrals
+    #[serde(rename = "401k")]
+    Traditional401k,
+    /// Traditional (not Roth) 403(b) deferrals
+    #[serde(rename = "403b")]
+    Traditional403b,
+    /// Traditional (not Roth) governmental 457(b) deferrals
+    #[serde(rename = "457b")]
+    Governmental457b,
+    /// Health savings account contributions made through payroll
+    Hsa,
+    /// Section 125 health, dental and vision insurance premiums
+    HealthPremiums,
+    /// Transit passes and qualified parking
+    CommuterBenefits,
+    /// Dependent care flexible spending account contributions
+    DependentCareFsa,
+    /// Any other deduction excluded from income tax but not from Social Security and Medicare.
+    /// The untyped `--pre-tax-deductions` are of this type.
+    Other,
+}
+
+const DEDUCTION_TYPES: [DeductionType; 8] = [
+    DeductionType::Traditional401k,
+    DeductionType::Traditional403b,
+    DeductionType::Governmental457b,
+    DeductionType::Hsa,
+    DeductionType::HealthPremiums,
+    DeductionType::CommuterBenefits,
+    DeductionType::DependentCareFsa,
+    DeductionType::Other,
+];
+
+impl DeductionType {
+    /// Whether the deduction is an elective deferral to a 401(k) or 403(b) plan, which employers
+    /// match and which counts towards the plan's 402(g) and 415(c) limits. Governmental 457(b)
+    /// plans have a separate limit of their own.
+    pub fn is_elective_deferral(&self) -> bool {
+        matches!(
+            self,
+            DeductionType::Traditional401k | DeductionType::Traditional403b
+        )
+    }
+
+    /// Whether Social Security and Medicare are still withheld on the deduction. Retirement
+    /// deferrals are FICA wages, cafeteria plan (Section 125) and commuter benefits are not.
+    pub fn is_fica_taxed(&self) -> bool {
+        matches!(
+            self,
+            DeductionType::Traditional401k
+                | DeductionType::Traditional403b
+                | DeductionType::Governmental457b
+                | DeductionType::Other
+        )
+    }
+
+    /// The name used in config files and the `--deduction` flag
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeductionType::Traditional401k => "401k",
+            DeductionType::Traditional403b => "403b",
+            DeductionType::Governmental457b => "457b",
+            DeductionType::Hsa => "hsa",
+            DeductionType::HealthPremiums => "health_premiums",
+            DeductionType::CommuterBenefits => "commuter_benefits",
+            DeductionType::DependentCareFsa => "dependent_care_fsa",
+            DeductionType::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for DeductionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A yearly amount taken out of pay before tax
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct PreTaxDeduction {
+    #[serde(rename = "type")]
+    pub deduction_type: DeductionType,
+    /// The yearly amount
+    pub amount: f64,
+    /// Whether the state taxes the deduction anyway, i.e. HSA contributions in California and
+    /// New Jersey. Most states follow the federal treatment.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub state_taxed: bool,
+}
+
+impl PreTaxDeduction {
+    pub fn new(deduction_type: DeductionType, amount: f64) -> Self {
+        Self {
+            deduction_type,
+            amount,
+            state_taxed: false,
+        }
+    }
+}
+
+/// Parses a deduction given as `TYPE=AMOUNT`, i.e. `401k=22500` or `hsa=3850`. A trailing
+/// `:state-taxed` marks a deduction the state taxes anyway, i.e. `hsa=3850:state-taxed`.
+impl FromStr for PreTaxDeduction {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (name, amount) = value
+            .split_once('=')
+            .ok_or_else(|| format!("Expected TYPE=AMOUNT, got {value:?}"))?;
+        let deduction_type = DEDUCTION_TYPES
+            .iter()
+            .find(|deduction_type| deduction_type.name() == name.trim())
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = DEDUCTION_TYPES.iter().map(DeductionType::name).collect();
+                format!(
+                    "Unknown deduction type {name:?}, expected one of {}",
+                    names.join(", ")
+                )
+            })?;
+        let (amount, state_taxed) = match amount.split_once(':') {
+            Some((amount, "state-taxed")) => (amount, true),
+            Some((_, flag)) => return Err(format!("Unknown deduction flag {flag:?}")),
+            None => (amount, false),
+        };
+        let amount: f64 = amount
+            .trim()
+            .parse()
+            .map_err(|_| format!("{amount:?} is not an amount"))?;
+        Ok(Self {
+            deduction_type,
+            amount,
+            state_taxed,
+        })
+    }
+}
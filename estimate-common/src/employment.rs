@@ -0,0 +1,87 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A job held for part or all of the year, as reported on its W-2
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Employment {
+    /// The name of the employer
+    pub employer: String,
+    /// The first day worked in the year
+    pub start_date: NaiveDate,
+    /// The last day worked in the year
+    pub end_date: NaiveDate,
+    /// Wages paid by the employer over the employment
+    pub wages: f64,
+    /// Federal tax withheld by the employer
+    #[serde(default)]
+    pub federal_withholding: f64,
+    /// State tax withheld by the employer
+    #[serde(default)]
+    pub state_withholding: f64,
+    /// Social Security tax withheld by the employer. Calculated from the wages when not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub social_security_withheld: Option<f64>,
+    /// Medicare tax withheld by the employer. Calculated from the wages when not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub medicare_withheld: Option<f64>,
+}
+
+/// The Social Security and Medicare tax withheld by a single employer
+pub struct EmployerFica {
+    pub employer: String,
+    pub wages: f64,
+    pub social_security: f64,
+    pub medicare: f64,
+}
+
+/// Struct representing the Social Security and Medicare tax withheld across every employer
+pub struct EmploymentResults {
+    pub employers: Vec<EmployerFica>,
+    /// The most Social Security tax owed on the year's wages
+    pub max_social_security: f64,
+    /// Social Security tax withheld by more than one employer beyond the most owed. It is
+    /// claimed back on the return as a credit against the federal tax.
+    pub excess_social_security: f64,
+}
+
+impl EmploymentResults {
+    /// Social Security tax withheld across every employer
+    pub fn total_social_security(&self) -> f64 {
+        self.employers
+            .iter()
+            .map(|employer| employer.social_security)
+            .sum()
+    }
+
+    /// Medicare tax withheld across every employer
+    pub fn total_medicare(&self) -> f64 {
+        self.employers
+            .iter()
+            .map(|employer| employer.medicare)
+            .sum()
+    }
+}
+
+impl fmt::Display for EmploymentResults {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for employer in self.employers.iter() {
+            writeln!(
+                f,
+                "Employer {}: wages {}, Social Security {}, Medicare {}",
+                employer.employer, employer.wages, employer.social_security, employer.medicare
+            )?;
+        }
+        writeln!(
+            f,
+            "Social Security Withheld: {}",
+            self.total_social_security()
+        )?;
+        writeln!(f, "Medicare Withheld: {}", self.total_medicare())?;
+        write!(
+            f,
+            "Excess Social Security Credit: {}",
+            self.excess_social_security
+        )
+    }
+}
@@ -0,0 +1,202 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A single vest of restricted stock units
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RsuVest {
+    pub vest_date: NaiveDate,
+    /// The number of shares vesting
+    pub shares: u64,
+    /// The assumed share price on the vest date
+    pub price: f64,
+}
+
+impl RsuVest {
+    /// The wages recognized by the vest
+    pub fn income(&self) -> f64 {
+        self.shares as f64 * self.price
+    }
+}
+
+/// The withholding taken from a single RSU vest by selling shares to cover it
+pub struct RsuVestResult {
+    pub vest_date: NaiveDate,
+    /// The wages recognized by the vest
+    pub income: f64,
+    /// The federal tax withheld at the flat supplemental rates
+    pub withheld: f64,
+    /// The whole shares sold to cover the withholding
+    pub shares_sold: u64,
+}
+
+/// Struct comparing the withholding taken from RSU vests to the federal tax they actually add
+pub struct RsuResults {
+    pub vests: Vec<RsuVestResult>,
+    /// Amount of federal tax added by all vests on top of the other wages
+    pub incremental_tax: f64,
+}
+
+impl RsuResults {
+    /// Federal tax withheld across all vests
+    pub fn total_withheld(&self) -> f64 {
+        self.vests.iter().map(|vest| vest.withheld).sum()
+    }
+
+    /// The amount withheld beyond the tax caused. Negative when too little is withheld.
+    pub fn withholding_gap(&self) -> f64 {
+        self.total_withheld() - self.incremental_tax
+    }
+}
+
+impl fmt::Display for RsuResults {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for vest in self.vests.iter() {
+            writeln!(
+                f,
+                "RSU Vest {}: income {}, withheld {}, shares sold {}",
+                vest.vest_date, vest.income, vest.withheld, vest.shares_sold
+            )?;
+        }
+        writeln!(f, "RSU Withholding: {}", self.total_withheld())?;
+        writeln!(f, "RSU Federal Taxes: {}", self.incremental_tax)?;
+        let gap = self.withholding_gap();
+        if gap >= 0.0 {
+            write!(f, "RSU Over-Withholding: {gap}")
+        } else {
+            write!(f, "RSU Under-Withholding: {}", -gap)
+        }
+    }
+}
+
+/// The default discount of an ESPP purchase price
+fn default_espp_discount_rate() -> f64 {
+    0.15
+}
+
+/// A sale of shares bought through an employee stock purchase plan
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EsppSale {
+    /// The start of the offering period the shares were bought in
+    pub offering_date: NaiveDate,
+    pub purchase_date: NaiveDate,
+    pub sale_date: NaiveDate,
+    pub shares: f64,
+    /// The share price on the offering date
+    pub offering_date_price: f64,
+    /// The share price on the purchase date
+    pub purchase_date_price: f64,
+    /// The discounted price actually paid per share
+    pub purchase_price: f64,
+    /// The price each share was sold at
+    pub sale_price: f64,
+    /// The plan's discount as a fraction. i.e. 0.15 for 15%
+    #[serde(default = "default_espp_discount_rate")]
+    pub discount_rate: f64,
+}
+
+/// An exercise of incentive stock options, along with the sale of the shares if they were sold
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IsoExercise {
+    pub grant_date: NaiveDate,
+    pub exercise_date: NaiveDate,
+    pub shares: f64,
+    /// The price paid per share to exercise
+    pub strike_price: f64,
+    /// The share price on the exercise date
+    pub exercise_date_price: f64,
+    /// When the shares were sold. Absent when the shares are still held.
+    #[serde(default)]
+    pub sale_date: Option<NaiveDate>,
+    /// The price each share was sold at. Absent when the shares are still held.
+    #[serde(default)]
+    pub sale_price: Option<f64>,
+}
+
+/// Whether a sale met the holding periods needed for preferential treatment
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DispositionType {
+    Qualifying,
+    Disqualifying,
+}
+
+impl fmt::Display for DispositionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DispositionType::Qualifying => write!(f, "qualifying"),
+            DispositionType::Disqualifying => write!(f, "disqualifying"),
+        }
+    }
+}
+
+/// The split of a single ESPP or ISO sale into ordinary income and capital gain
+pub struct DispositionResult {
+    /// What was sold. i.e. "ESPP" or "ISO"
+    pub plan: String,
+    pub sale_date: NaiveDate,
+    pub disposition_type: DispositionType,
+    /// Compensation taxed as wages
+    pub ordinary_income: f64,
+    /// Gain (or loss when negative) beyond the ordinary income. Reported only, as capital gains
+    /// are not part of the estimated tax.
+    pub capital_gain: f64,
+    /// Whether the capital gain is long term
+    pub is_long_term: bool,
+}
+
+/// Struct representing the income from ESPP and ISO sales, and ISO exercises held past year end
+pub struct EquityDispositionResults {
+    pub dispositions: Vec<DispositionResult>,
+    /// The AMT preference item from ISO shares exercised and held past the end of the year
+    pub amt_preference: f64,
+}
+
+impl EquityDispositionResults {
+    /// Ordinary income across all sales
+    pub fn total_ordinary_income(&self) -> f64 {
+        self.dispositions
+            .iter()
+            .map(|disposition| disposition.ordinary_income)
+            .sum()
+    }
+
+    /// Capital gains across all sales. Not taxed by the estimate, which only covers wages.
+    pub fn total_capital_gain(&self) -> f64 {
+        self.dispositions
+            .iter()
+            .map(|disposition| disposition.capital_gain)
+            .sum()
+    }
+}
+
+impl fmt::Display for EquityDispositionResults {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for disposition in self.dispositions.iter() {
+            writeln!(
+                f,
+                "{} Sale {} ({}): ordinary income {}, {} capital gain {}",
+                disposition.plan,
+                disposition.sale_date,
+                disposition.disposition_type,
+                disposition.ordinary_income,
+                if disposition.is_long_term {
+                    "long term"
+                } else {
+                    "short term"
+                },
+                disposition.capital_gain
+            )?;
+        }
+        writeln!(
+            f,
+            "Equity Ordinary Income: {}",
+            self.total_ordinary_income()
+        )?;
+        writeln!(
+            f,
+            "Equity Capital Gains (not taxed in this estimate): {}",
+            self.total_capital_gain()
+        )?;
+        write!(f, "ISO AMT Preference: {}", self.amt_preference)
+    }
+}
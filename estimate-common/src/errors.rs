@@ -24,14 +24,20 @@ pub enum EstimaterErrors {
 
 #[derive(Error, Debug)]
 pub enum BracketErrors {
-    #[error("Errors due to the income not meeting minimum requirement of a bracket.")]
+    #[error("Errors due to the income not meeting minimum requirement of a bracket: {0}")]
     SmallIncomeError(String),
-    #[error("Errors due to the income exceeding the maximum allowed by a bracket.")]
+    #[error("Errors due to the income exceeding the maximum allowed by a bracket: {0}")]
     LargeIncomeError(String),
-    #[error("Errors due to tax rate not being within [0, 1].")]
+    #[error("Errors due to tax rate not being within [0, 1]: {0}")]
     TaxRateError(String),
-    #[error("Errors due to bracket min and max")]
+    #[error("Errors due to bracket min and max: {0}")]
     RangeError(String),
-    #[error("Errors due to overlap of two brackets")]
+    #[error("Errors due to overlap of two brackets: {0}")]
     OverlapError(String),
+    #[error("Errors due to a gap between two brackets: {0}")]
+    GapError(String),
+    #[error("Errors due to the cumulative previous tax not matching the brackets before it: {0}")]
+    CumulativeTaxError(String),
+    #[error("Errors due to a bracket not being in the expected format: {0}")]
+    FormatError(String),
 }
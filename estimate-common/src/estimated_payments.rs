@@ -0,0 +1,176 @@
+use chrono::NaiveDate;
+use clap::Args;
+use serde::Deserialize;
+use std::fmt;
+
+/// Information about the previous tax year and current withholding needed to plan
+/// quarterly estimated tax payments.
+#[derive(Args, Clone, Debug, Deserialize)]
+pub struct SafeHarborInfo {
+    #[clap(long = "tax-year")]
+    /// The year payments are being planned for
+    pub tax_year: i32,
+    #[clap(long = "prior-year-tax")]
+    /// Total federal tax of the previous year
+    pub prior_year_tax: f64,
+    #[clap(long = "prior-year-agi")]
+    /// Adjusted gross income of the previous year
+    pub prior_year_agi: f64,
+    #[clap(long = "withholding", default_value_t = 0.0)]
+    /// Federal tax withheld from wages over the current year
+    #[serde(default)]
+    pub withholding: f64,
+}
+
+/// The safe harbor rule that sets the required annual payment
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SafeHarborRule {
+    /// 90% of the current year's tax
+    CurrentYear,
+    /// 100% of the prior year's tax
+    PriorYear,
+    /// 110% of the prior year's tax, used when the prior year's AGI was high
+    PriorYearHighIncome,
+}
+
+impl fmt::Display for SafeHarborRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rule = match self {
+            SafeHarborRule::CurrentYear => "90% of current year tax",
+            SafeHarborRule::PriorYear => "100% of prior year tax",
+            SafeHarborRule::PriorYearHighIncome => "110% of prior year tax",
+        };
+        write!(f, "{rule}")
+    }
+}
+
+/// A single quarterly estimated payment
+pub struct EstimatedPayment {
+    pub due_date: NaiveDate,
+    pub amount: f64,
+}
+
+/// Struct representing the estimated payments needed to avoid an underpayment penalty
+pub struct EstimatedPaymentPlan {
+    /// The federal tax estimated for the current year
+    pub current_year_tax: f64,
+    /// The total that must be paid through withholding and estimated payments
+    pub required_annual_payment: f64,
+    /// The rule used to determine the required annual payment
+    pub safe_harbor_rule: SafeHarborRule,
+    /// The quarterly payments. Amounts are 0 when no estimated payments are required.
+    pub installments: Vec<EstimatedPayment>,
+}
+
+impl EstimatedPaymentPlan {
+    /// The sum of all quarterly payments
+    pub fn total_estimated_payments(&self) -> f64 {
+        self.installments.iter().map(|payment| payment.amount).sum()
+    }
+}
+
+impl fmt::Display for EstimatedPaymentPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Current Year Tax: {}", self.current_year_tax)?;
+        writeln!(
+            f,
+            "Required Annual Payment: {} ({})",
+            self.required_annual_payment, self.safe_harbor_rule
+        )?;
+        for payment in self.installments.iter() {
+            writeln!(f, "Due {}: {}", payment.due_date, payment.amount)?;
+        }
+        write!(
+            f,
+            "Total Estimated Payments: {}",
+            self.total_estimated_payments()
+        )
+    }
+}
+
+/// A single payment made towards a year's taxes
+#[derive(Clone, Debug, Deserialize)]
+pub struct PaymentRecord {
+    pub date: NaiveDate,
+    pub amount: f64,
+    /// Whether the payment was withheld from wages instead of paid as an estimated payment
+    #[serde(default)]
+    pub is_withholding: bool,
+}
+
+/// The payments made over a year, used to estimate the penalty for underpaying
+#[derive(Clone, Debug, Deserialize)]
+pub struct UnderpaymentInfo {
+    /// The year the payments were made for
+    pub tax_year: i32,
+    /// The total that needed to be paid through withholding and estimated payments
+    pub required_annual_payment: f64,
+    /// Withholding without specific dates. Treated as paid in equal amounts on each due date.
+    #[serde(default)]
+    pub withholding: f64,
+    /// Payments (or withholding) made on specific dates
+    #[serde(default)]
+    pub payments: Vec<PaymentRecord>,
+}
+
+impl UnderpaymentInfo {
+    /// The sum of all withholding, with or without dates
+    pub fn total_withholding(&self) -> f64 {
+        self.withholding
+            + self
+                .payments
+                .iter()
+                .filter(|payment| payment.is_withholding)
+                .map(|payment| payment.amount)
+                .sum::<f64>()
+    }
+}
+
+/// The underpayment of a single quarterly installment
+pub struct QuarterUnderpayment {
+    pub due_date: NaiveDate,
+    /// The amount that needed to be paid by the due date
+    pub required_installment: f64,
+    /// The amount credited towards this installment by the due date
+    pub paid: f64,
+    /// The amount of the installment still unpaid at the due date
+    pub underpayment: f64,
+    /// The interest charged on the underpayment until it was paid
+    pub penalty: f64,
+}
+
+/// Struct representing the estimated penalty for underpaying over a year
+pub struct UnderpaymentPenalty {
+    /// The total that needed to be paid through withholding and estimated payments
+    pub required_annual_payment: f64,
+    pub quarters: Vec<QuarterUnderpayment>,
+}
+
+impl UnderpaymentPenalty {
+    /// The penalty across all quarters
+    pub fn total_penalty(&self) -> f64 {
+        self.quarters.iter().map(|quarter| quarter.penalty).sum()
+    }
+}
+
+impl fmt::Display for UnderpaymentPenalty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Required Annual Payment: {}",
+            self.required_annual_payment
+        )?;
+        for quarter in self.quarters.iter() {
+            writeln!(
+                f,
+                "Due {}: required {}, paid {}, underpaid {}, penalty {}",
+                quarter.due_date,
+                quarter.required_installment,
+                quarter.paid,
+                quarter.underpayment,
+                quarter.penalty
+            )?;
+        }
+        write!(f, "Total Penalty: {}", self.total_penalty())
+    }
+}
@@ -0,0 +1,158 @@
+//! Reading and writing the files used by the client and server in any of the supported formats.
+//! The format of a file is chosen by its extension.
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, fs, path::Path};
+
+use crate::errors::{EstimaterErrors, EstimaterResult};
+
+/// The formats a file may be written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl FileFormat {
+    /// Determines the format of a file from its extension. Files without a known extension are
+    /// treated as json.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("toml") => FileFormat::Toml,
+            Some("yaml") | Some("yml") => FileFormat::Yaml,
+            _ => FileFormat::Json,
+        }
+    }
+
+    /// Parses the contents of a file in this format into the given struct
+    ///
+    /// # Return
+    ///
+    /// * `EstimaterErrors::ParsingError` describing where the contents are wrong
+    pub fn parse<T: DeserializeOwned>(&self, contents: &str) -> EstimaterResult<T> {
+        let parsed = match self {
+            FileFormat::Json => serde_json::from_str(contents).map_err(|err| err.to_string()),
+            FileFormat::Toml => toml::from_str(contents).map_err(|err| err.to_string()),
+            FileFormat::Yaml => serde_yaml::from_str(contents).map_err(|err| err.to_string()),
+        };
+        parsed.map_err(|err| EstimaterErrors::ParsingError(format!("Invalid {self}: {err}")))
+    }
+
+    /// Writes the given struct in this format. Json is written with its keys sorted.
+    pub fn write<T: Serialize>(&self, value: &T) -> EstimaterResult<String> {
+        let written = match self {
+            FileFormat::Json => serde_json::to_value(value)
+                .and_then(|value| serde_json::to_string_pretty(&value))
+                .map_err(|err| err.to_string()),
+            FileFormat::Toml => toml::to_string_pretty(value).map_err(|err| err.to_string()),
+            FileFormat::Yaml => serde_yaml::to_string(value).map_err(|err| err.to_string()),
+        };
+        written
+            .map(|contents| format!("{}\n", contents.trim_end()))
+            .map_err(|err| EstimaterErrors::ParsingError(format!("Could not write {self}: {err}")))
+    }
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileFormat::Json => write!(f, "json"),
+            FileFormat::Toml => write!(f, "toml"),
+            FileFormat::Yaml => write!(f, "yaml"),
+        }
+    }
+}
+
+/// Reads a json, toml or yaml file (by its extension) into the given struct
+///
+/// # Return
+///
+/// * `EstimaterErrors::FileError` when the file could not be read
+/// * `EstimaterErrors::ParsingError` when the file is not in the expected format
+pub fn read_file<T: DeserializeOwned>(path: &Path) -> EstimaterResult<T> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| EstimaterErrors::FileError(format!("Could not read {:?}: {err}", path)))?;
+    FileFormat::from_path(path)
+        .parse(&contents)
+        .map_err(|err| match err {
+            EstimaterErrors::ParsingError(msg) => {
+                EstimaterErrors::ParsingError(format!("Could not parse {:?}. {msg}", path))
+            }
+            err => err,
+        })
+}
+
+/// Writes the given struct to a json, toml or yaml file (by its extension)
+pub fn write_file<T: Serialize>(path: &Path, value: &T) -> EstimaterResult<()> {
+    let contents = FileFormat::from_path(path).write(value)?;
+    fs::write(path, contents)
+        .map_err(|err| EstimaterErrors::FileError(format!("Could not write {:?}: {err}", path)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::common::{FilingStatus, TaxInfo};
+
+    #[test]
+    fn test_format_from_extension() {
+        for (path, expected) in [
+            ("info.json", FileFormat::Json),
+            ("info.toml", FileFormat::Toml),
+            ("info.TOML", FileFormat::Toml),
+            ("info.yaml", FileFormat::Yaml),
+            ("info.yml", FileFormat::Yaml),
+        ] {
+            assert_eq!(FileFormat::from_path(Path::new(path)), expected, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_unknown_extensions_default_to_json() {
+        for path in ["info", "info.txt", "info.json.bak"] {
+            assert_eq!(FileFormat::from_path(Path::new(path)), FileFormat::Json);
+        }
+    }
+
+    #[test]
+    fn test_parse_tax_info() {
+        let toml_str = r#"
+gross_yearly_income = 100000
+state_tax_rate_percent = 5.0
+filing_status = "married_filing_jointly"
+"#;
+        let yaml_str = r#"
+gross_yearly_income: 100000
+state_tax_rate_percent: 5.0
+filing_status: married_filing_jointly
+"#;
+        for (format, contents) in [(FileFormat::Toml, toml_str), (FileFormat::Yaml, yaml_str)] {
+            let info: TaxInfo = format
+                .parse(contents)
+                .unwrap_or_else(|err| panic!("Parsing {format} failed: {err}"));
+            assert_eq!(info.gross_yearly_income, 100000, "{format}");
+            assert_eq!(info.state_tax_rate_percent, 5.0, "{format}");
+            assert_eq!(
+                info.filing_status,
+                FilingStatus::MarriedFilingJointly,
+                "{format}"
+            );
+            assert!(info.deductions.is_empty(), "{format}");
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_format() {
+        let result = FileFormat::Toml.parse::<TaxInfo>("gross_yearly_income = \"lots\"");
+        assert!(
+            matches!(&result, Err(EstimaterErrors::ParsingError(msg)) if msg.starts_with("Invalid toml")),
+            "Got {:?}",
+            result.err()
+        );
+    }
+}
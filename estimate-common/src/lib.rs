@@ -1,2 +1,14 @@
+pub mod benefits;
 pub mod common;
+pub mod deductions;
+pub mod employment;
+pub mod equity;
 pub mod errors;
+pub mod estimated_payments;
+pub mod file_format;
+pub mod paycheck;
+pub mod projection;
+pub mod retirement;
+pub mod scenarios;
+pub mod utils;
+pub mod w4;
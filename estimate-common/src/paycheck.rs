@@ -0,0 +1,255 @@
+use chrono::NaiveDate;
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How often wages are paid
+#[derive(ValueEnum, Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PayFrequency {
+    Weekly,
+    #[default]
+    Biweekly,
+    SemiMonthly,
+    Monthly,
+}
+
+impl PayFrequency {
+    /// The number of paychecks in a year
+    pub fn periods_per_year(&self) -> u32 {
+        match self {
+            PayFrequency::Weekly => 52,
+            PayFrequency::Biweekly => 26,
+            PayFrequency::SemiMonthly => 24,
+            PayFrequency::Monthly => 12,
+        }
+    }
+}
+
+impl fmt::Display for PayFrequency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let frequency = match self {
+            PayFrequency::Weekly => "weekly",
+            PayFrequency::Biweekly => "biweekly",
+            PayFrequency::SemiMonthly => "semi-monthly",
+            PayFrequency::Monthly => "monthly",
+        };
+        write!(f, "{frequency}")
+    }
+}
+
+/// How wages are paid out, and what is taken out of each paycheck besides taxes
+#[derive(Args, Clone, Debug, Default, Deserialize)]
+pub struct PaycheckInfo {
+    #[clap(long = "pay-frequency", value_enum, default_value_t = PayFrequency::Biweekly)]
+    /// How often wages are paid. Defaults to biweekly.
+    #[serde(default)]
+    pub pay_frequency: PayFrequency,
+    #[clap(long = "paycheck-pre-tax-deductions", default_value_t = 0.0)]
+    /// Amount taken out of each paycheck before income tax, i.e. traditional 401(k)
+    /// contributions. Social Security and Medicare are still withheld on it.
+    #[serde(default)]
+    pub pre_tax_deductions: f64,
+    #[clap(long = "paycheck-post-tax-deductions", default_value_t = 0.0)]
+    /// Amount taken out of each paycheck after taxes, i.e. Roth 401(k) contributions
+    #[serde(default)]
+    pub post_tax_deductions: f64,
+}
+
+/// When paychecks are paid over a tax year
+#[derive(Args, Clone, Debug, Deserialize)]
+pub struct PaycheckScheduleInfo {
+    #[clap(long = "tax-year")]
+    /// The year to list the paychecks of
+    pub tax_year: i32,
+    #[clap(long = "pay-frequency", value_enum, default_value_t = PayFrequency::Biweekly)]
+    /// How often wages are paid. Defaults to biweekly.
+    #[serde(default)]
+    pub pay_frequency: PayFrequency,
+    #[clap(long = "first-pay-date")]
+    /// The first weekly or biweekly pay date of the year (YYYY-MM-DD). Defaults to the first
+    /// Friday. Semi-monthly paychecks are paid on the 15th and last day of the month, and
+    /// monthly paychecks on the last day.
+    #[serde(default)]
+    pub first_pay_date: Option<NaiveDate>,
+}
+
+/// Struct representing a single paycheck, from its gross pay down to its net pay
+#[derive(Default)]
+pub struct PaycheckWithholding {
+    /// Wages paid in the paycheck before anything is taken out
+    pub gross_pay: f64,
+    /// Amount taken out before income tax
+    pub pre_tax_deductions: f64,
+    /// Federal income tax withheld
+    pub federal_withholding: f64,
+    /// State income tax withheld
+    pub state_withholding: f64,
+    /// Social Security tax withheld
+    pub social_security: f64,
+    /// Medicare tax withheld
+    pub medicare: f64,
+    /// Amount taken out after taxes
+    pub post_tax_deductions: f64,
+}
+
+impl PaycheckWithholding {
+    /// Every tax withheld from the paycheck
+    pub fn total_withholding(&self) -> f64 {
+        self.federal_withholding + self.state_withholding + self.social_security + self.medicare
+    }
+
+    /// The amount actually paid out
+    pub fn net_pay(&self) -> f64 {
+        self.gross_pay
+            - self.pre_tax_deductions
+            - self.total_withholding()
+            - self.post_tax_deductions
+    }
+}
+
+impl fmt::Display for PaycheckWithholding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Gross Pay: {}", self.gross_pay)?;
+        writeln!(f, "Pre-Tax Deductions: {}", self.pre_tax_deductions)?;
+        writeln!(f, "Federal Withholding: {}", self.federal_withholding)?;
+        writeln!(f, "State Withholding: {}", self.state_withholding)?;
+        writeln!(f, "Social Security: {}", self.social_security)?;
+        writeln!(f, "Medicare: {}", self.medicare)?;
+        writeln!(f, "Post-Tax Deductions: {}", self.post_tax_deductions)?;
+        write!(f, "Net Pay: {}", self.net_pay())
+    }
+}
+
+/// Struct representing the salary implied by a net paycheck
+pub struct PaycheckSolution {
+    /// The net pay asked for
+    pub target_net_pay: f64,
+    pub pay_frequency: PayFrequency,
+    /// The smallest whole dollar gross yearly income with a net pay of at least the target
+    pub gross_yearly_income: u64,
+    /// A paycheck at `gross_yearly_income`
+    pub paycheck: PaycheckWithholding,
+}
+
+impl fmt::Display for PaycheckSolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Target Net Pay: {} ({})",
+            self.target_net_pay, self.pay_frequency
+        )?;
+        writeln!(
+            f,
+            "Gross Yearly Income Needed: {}",
+            self.gross_yearly_income
+        )?;
+        write!(f, "{}", self.paycheck)
+    }
+}
+
+/// A paycheck paid on a given day
+pub struct ScheduledPaycheck {
+    pub pay_date: NaiveDate,
+    pub paycheck: PaycheckWithholding,
+    /// Whether the year's wages reached the Social Security wage base in this paycheck, after
+    /// which no more Social Security tax is withheld
+    pub reaches_wage_base: bool,
+}
+
+/// Struct representing every regular paycheck over a tax year
+pub struct PaycheckSchedule {
+    pub pay_frequency: PayFrequency,
+    /// The wages Social Security tax is withheld on over the year
+    pub social_security_wage_base: f64,
+    pub paychecks: Vec<ScheduledPaycheck>,
+}
+
+impl PaycheckSchedule {
+    /// Every paycheck added together
+    pub fn totals(&self) -> PaycheckWithholding {
+        self.paychecks
+            .iter()
+            .map(|scheduled| &scheduled.paycheck)
+            .fold(PaycheckWithholding::default(), |total, paycheck| {
+                PaycheckWithholding {
+                    gross_pay: total.gross_pay + paycheck.gross_pay,
+                    pre_tax_deductions: total.pre_tax_deductions + paycheck.pre_tax_deductions,
+                    federal_withholding: total.federal_withholding + paycheck.federal_withholding,
+                    state_withholding: total.state_withholding + paycheck.state_withholding,
+                    social_security: total.social_security + paycheck.social_security,
+                    medicare: total.medicare + paycheck.medicare,
+                    post_tax_deductions: total.post_tax_deductions + paycheck.post_tax_deductions,
+                }
+            })
+    }
+}
+
+impl fmt::Display for PaycheckSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} paychecks ({}). Social Security wage base: {}",
+            self.paychecks.len(),
+            self.pay_frequency,
+            self.social_security_wage_base
+        )?;
+        writeln!(
+            f,
+            "{:<12}{:>12}{:>12}{:>12}{:>12}{:>13}{:>12}{:>12}{:>12}",
+            "Pay Date",
+            "Gross",
+            "Pre-Tax",
+            "Federal",
+            "State",
+            "Soc. Sec.",
+            "Medicare",
+            "Post-Tax",
+            "Net Pay"
+        )?;
+        for scheduled in self.paychecks.iter() {
+            write_paycheck_row(
+                f,
+                &scheduled.pay_date.to_string(),
+                &scheduled.paycheck,
+                if scheduled.reaches_wage_base {
+                    "*"
+                } else {
+                    " "
+                },
+            )?;
+        }
+        write_paycheck_row(f, "Total", &self.totals(), " ")?;
+        if self
+            .paychecks
+            .iter()
+            .any(|scheduled| scheduled.reaches_wage_base)
+        {
+            write!(f, "* Wages reach the Social Security wage base")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes one row of the paycheck schedule table
+fn write_paycheck_row(
+    f: &mut fmt::Formatter,
+    label: &str,
+    paycheck: &PaycheckWithholding,
+    wage_base_marker: &str,
+) -> fmt::Result {
+    writeln!(
+        f,
+        "{:<12}{:>12.2}{:>12.2}{:>12.2}{:>12.2}{:>12.2}{}{:>12.2}{:>12.2}{:>12.2}",
+        label,
+        paycheck.gross_pay,
+        paycheck.pre_tax_deductions,
+        paycheck.federal_withholding,
+        paycheck.state_withholding,
+        paycheck.social_security,
+        wage_base_marker,
+        paycheck.medicare,
+        paycheck.post_tax_deductions,
+        paycheck.net_pay()
+    )
+}
@@ -0,0 +1,112 @@
+use clap::Args;
+use serde::Deserialize;
+use std::fmt;
+
+/// How income, the federal brackets and retirement contributions change over the years
+#[derive(Args, Clone, Debug, Deserialize)]
+pub struct ProjectionInfo {
+    #[clap(long = "tax-year")]
+    /// The tax year of the first projected year, whose 401(k) limit caps the contributions
+    pub tax_year: i32,
+    #[clap(long = "years")]
+    /// The number of years to project, starting with the year of the config file
+    pub years: u32,
+    #[clap(long = "salary-growth", default_value_t = 0.0)]
+    /// The yearly raise of the salary, hourly rate and bonus, as a %
+    #[serde(default)]
+    pub salary_growth_percent: f64,
+    #[clap(long = "bracket-inflation", default_value_t = 0.0)]
+    /// The yearly inflation adjustment of the federal bracket thresholds, as a %
+    #[serde(default)]
+    pub bracket_inflation_percent: f64,
+    #[clap(long = "contribution-percent")]
+    /// Traditional 401(k) deferrals as a % of the salary and hourly pay, up to the year's
+    /// elective deferral limit. Replaces the 401(k) deductions of the config file, the other
+    /// pre-tax deductions are kept. When not given, the 401(k) deductions of the config file
+    /// are kept every year.
+    #[serde(default)]
+    pub contribution_percent: Option<f64>,
+    #[clap(long = "contribution-increase", default_value_t = 0.0)]
+    /// The % points added to the contribution percent each year, i.e. for automatic escalation
+    #[serde(default)]
+    pub contribution_increase_percent: f64,
+}
+
+/// The taxes of a single projected year
+pub struct ProjectedYear {
+    /// The year of the projection, starting at 1
+    pub year: u32,
+    /// All wages, regular and supplemental
+    pub wages: f64,
+    /// Every pre-tax deduction, the retirement contributions included
+    pub pre_tax_deductions: f64,
+    pub federal_tax: f64,
+    pub state_tax: f64,
+    pub net_income: f64,
+}
+
+/// Struct representing the taxes and net income of each year of a projection
+pub struct Projection {
+    pub salary_growth_percent: f64,
+    pub bracket_inflation_percent: f64,
+    pub years: Vec<ProjectedYear>,
+}
+
+impl Projection {
+    /// Every year added together
+    pub fn totals(&self) -> ProjectedYear {
+        self.years.iter().fold(
+            ProjectedYear {
+                year: 0,
+                wages: 0.0,
+                pre_tax_deductions: 0.0,
+                federal_tax: 0.0,
+                state_tax: 0.0,
+                net_income: 0.0,
+            },
+            |total, year| ProjectedYear {
+                year: total.year + 1,
+                wages: total.wages + year.wages,
+                pre_tax_deductions: total.pre_tax_deductions + year.pre_tax_deductions,
+                federal_tax: total.federal_tax + year.federal_tax,
+                state_tax: total.state_tax + year.state_tax,
+                net_income: total.net_income + year.net_income,
+            },
+        )
+    }
+}
+
+impl fmt::Display for Projection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} years. Salary growth: {}%, bracket inflation: {}%",
+            self.years.len(),
+            self.salary_growth_percent,
+            self.bracket_inflation_percent
+        )?;
+        writeln!(
+            f,
+            "{:<8}{:>14}{:>14}{:>14}{:>14}{:>14}",
+            "Year", "Wages", "Pre-Tax", "Federal", "State", "Net Income"
+        )?;
+        for year in self.years.iter() {
+            write_projection_row(f, &year.year.to_string(), year)?;
+        }
+        write_projection_row(f, "Total", &self.totals())
+    }
+}
+
+/// Writes one row of the projection table
+fn write_projection_row(f: &mut fmt::Formatter, label: &str, year: &ProjectedYear) -> fmt::Result {
+    writeln!(
+        f,
+        "{:<8}{:>14.2}{:>14.2}{:>14.2}{:>14.2}{:>14.2}",
+        label,
+        year.wages,
+        year.pre_tax_deductions,
+        year.federal_tax,
+        year.state_tax,
+        year.net_income
+    )
+}
@@ -0,0 +1,116 @@
+use clap::Args;
+use serde::Deserialize;
+use std::fmt;
+
+/// The year of a 401(k) plan, and who is contributing to it
+#[derive(Args, Clone, Debug, Deserialize)]
+pub struct RetirementPlanInfo {
+    #[clap(long = "tax-year")]
+    /// The year whose contribution limits apply
+    pub tax_year: i32,
+    #[clap(long = "catch-up")]
+    /// Whether age 50 or over by the end of the year, which allows catch-up deferrals
+    #[serde(default)]
+    pub catch_up_eligible: bool,
+}
+
+/// Struct comparing the contributions to a 401(k) plan to its limits.
+///
+/// Elective deferrals (pre-tax and Roth) are limited on their own by section 402(g). Together
+/// with the employer match and after-tax contributions they are annual additions, limited by
+/// section 415(c) to the lesser of a dollar amount and the year's pay. Catch-up deferrals
+/// count towards neither.
+pub struct AnnualAdditions {
+    pub tax_year: i32,
+    /// Pre-tax and Roth deferrals over the year
+    pub elective_deferrals: f64,
+    /// The most that can be deferred, before any catch-up deferrals
+    pub elective_deferral_limit: f64,
+    /// The most that can be deferred beyond the limit. 0 when not eligible.
+    pub catch_up_limit: f64,
+    /// The employer's match on the elective deferrals
+    pub employer_match: f64,
+    /// After-tax (non-Roth) contributions over the year
+    pub after_tax_contributions: f64,
+    /// The lesser of the year's dollar limit on annual additions and the pay
+    pub annual_additions_limit: f64,
+}
+
+impl AnnualAdditions {
+    /// The deferrals beyond the elective deferral limit, up to the catch-up limit
+    pub fn catch_up_deferrals(&self) -> f64 {
+        (self.elective_deferrals - self.elective_deferral_limit).clamp(0.0, self.catch_up_limit)
+    }
+
+    /// The deferrals beyond both the elective deferral and catch-up limits, which have to be
+    /// returned
+    pub fn excess_deferrals(&self) -> f64 {
+        (self.elective_deferrals - self.elective_deferral_limit - self.catch_up_limit).max(0.0)
+    }
+
+    /// The contributions counting towards the annual additions limit
+    pub fn annual_additions(&self) -> f64 {
+        self.elective_deferrals.min(self.elective_deferral_limit)
+            + self.employer_match
+            + self.after_tax_contributions
+    }
+
+    /// How much more can be contributed after tax, i.e. for a mega-backdoor Roth
+    pub fn remaining_after_tax_room(&self) -> f64 {
+        (self.annual_additions_limit - self.annual_additions()).max(0.0)
+    }
+
+    /// The annual additions beyond the limit, which have to be returned
+    pub fn excess_additions(&self) -> f64 {
+        (self.annual_additions() - self.annual_additions_limit).max(0.0)
+    }
+}
+
+impl fmt::Display for AnnualAdditions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Elective Deferrals: {} (limit {} for {})",
+            self.elective_deferrals, self.elective_deferral_limit, self.tax_year
+        )?;
+        if self.catch_up_limit > 0.0 {
+            writeln!(
+                f,
+                "Catch-Up Deferrals: {} (limit {})",
+                self.catch_up_deferrals(),
+                self.catch_up_limit
+            )?;
+        }
+        writeln!(f, "Employer Match: {}", self.employer_match)?;
+        writeln!(
+            f,
+            "After-Tax Contributions: {}",
+            self.after_tax_contributions
+        )?;
+        writeln!(
+            f,
+            "Annual Additions: {} (limit {})",
+            self.annual_additions(),
+            self.annual_additions_limit
+        )?;
+        if self.excess_deferrals() > 0.0 {
+            writeln!(
+                f,
+                "Excess Deferrals To Be Returned: {}",
+                self.excess_deferrals()
+            )?;
+        }
+        if self.excess_additions() > 0.0 {
+            writeln!(
+                f,
+                "Excess Annual Additions To Be Returned: {}",
+                self.excess_additions()
+            )?;
+        }
+        write!(
+            f,
+            "Remaining After-Tax Room: {}",
+            self.remaining_after_tax_room()
+        )
+    }
+}
@@ -0,0 +1,88 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fmt;
+
+use crate::common::TaxResults;
+
+/// The name given to the base scenario in results
+pub const BASE_SCENARIO_NAME: &str = "base";
+
+/// A config file describing a base scenario and named variants of it
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScenarioFile {
+    /// The fields of the base scenario, in the same format as a single config file
+    pub base: Map<String, Value>,
+    /// Variants of the base scenario, i.e. "max 401k" or "move to Texas"
+    #[serde(default)]
+    pub variants: Vec<ScenarioVariant>,
+}
+
+/// A named variant of the base scenario
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScenarioVariant {
+    pub name: String,
+    /// The fields of the base scenario this variant replaces. Lists (i.e. `rsu_vests`) are
+    /// replaced as a whole.
+    #[serde(flatten)]
+    pub overrides: Map<String, Value>,
+}
+
+/// The taxes of a single scenario
+pub struct ScenarioResult {
+    pub name: String,
+    pub results: TaxResults,
+}
+
+impl ScenarioResult {
+    /// All income, before any taxes are taken out
+    pub fn income(&self) -> f64 {
+        self.results.net_income + self.results.total_tax()
+    }
+}
+
+/// Struct comparing the taxes of a base scenario (first) against each of its variants
+pub struct ScenarioComparison {
+    pub scenarios: Vec<ScenarioResult>,
+}
+
+impl ScenarioComparison {
+    /// How much more net income a scenario has than the base scenario
+    pub fn net_income_change(&self, scenario: &ScenarioResult) -> f64 {
+        let base_net_income = self
+            .scenarios
+            .first()
+            .map_or(0.0, |base| base.results.net_income);
+        scenario.results.net_income - base_net_income
+    }
+}
+
+impl fmt::Display for ScenarioComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name_width = self
+            .scenarios
+            .iter()
+            .map(|scenario| scenario.name.len())
+            .chain(["Scenario".len()])
+            .max()
+            .unwrap_or_default();
+        write!(
+            f,
+            "{:<name_width$} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+            "Scenario", "Income", "Federal Tax", "State Tax", "Total Tax", "Net Income", "vs Base"
+        )?;
+        for scenario in self.scenarios.iter() {
+            write!(
+                f,
+                "\n{:<name_width$} {:>12.2} {:>12.2} {:>12.2} {:>12.2} {:>12.2} {:>+12.2}",
+                scenario.name,
+                scenario.income(),
+                scenario.results.federal_tax,
+                scenario.results.state_tax,
+                scenario.results.total_tax(),
+                scenario.results.net_income,
+                self.net_income_change(scenario)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+//! Small helpers shared by the calculations and their results.
+
+/// A lot of tax documents only use 2 decimal sig-figs. To align our
+/// calculations, the same is being repeated here.
+pub fn round_to_hundredths(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
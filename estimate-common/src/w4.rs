@@ -0,0 +1,92 @@
+use clap::Args;
+use serde::Deserialize;
+use std::fmt;
+
+use crate::paycheck::PayFrequency;
+
+/// The entries of a 2020 or later Form W-4, and the refund to aim for
+#[derive(Args, Clone, Debug, Default, Deserialize)]
+pub struct W4Info {
+    #[clap(long = "pay-frequency", value_enum, default_value_t = PayFrequency::Biweekly)]
+    /// How often wages are paid. Defaults to biweekly.
+    #[serde(default)]
+    pub pay_frequency: PayFrequency,
+    #[clap(long = "multiple-jobs")]
+    /// Step 2(c): the box checked for two jobs at once, or a spouse who also works
+    #[serde(default)]
+    pub multiple_jobs: bool,
+    #[clap(long = "dependents-credit", default_value_t = 0.0)]
+    /// Step 3: the yearly credit claimed for dependents
+    #[serde(default)]
+    pub dependents_credit: f64,
+    #[clap(long = "other-income", default_value_t = 0.0)]
+    /// Step 4(a): other yearly income not from jobs, i.e. interest and dividends
+    #[serde(default)]
+    pub other_income: f64,
+    #[clap(long = "w4-deductions", default_value_t = 0.0)]
+    /// Step 4(b): yearly deductions expected beyond the standard deduction
+    #[serde(default)]
+    pub deductions: f64,
+    #[clap(long = "extra-withholding", default_value_t = 0.0)]
+    /// Step 4(c): the extra amount withheld from each paycheck
+    #[serde(default)]
+    pub extra_withholding: f64,
+    #[clap(long = "target-refund", default_value_t = 0.0)]
+    /// The federal refund to aim for. Negative to aim to owe instead.
+    #[serde(default)]
+    pub target_refund: f64,
+}
+
+/// Struct comparing the federal withholding of a W-4 to the federal tax of the year
+pub struct W4Analysis {
+    pub pay_frequency: PayFrequency,
+    /// Federal tax withheld from each regular paycheck, including the Step 4(c) extra
+    pub withholding_per_paycheck: f64,
+    /// Federal tax withheld from supplemental wages (i.e. bonuses) at the flat rates
+    pub supplemental_withholding: f64,
+    /// Federal tax withheld over the year, from paychecks and supplemental wages
+    pub annual_withholding: f64,
+    /// The federal tax of the year
+    pub federal_tax: f64,
+    /// The refund to aim for
+    pub target_refund: f64,
+    /// The Step 4(c) amount per paycheck that reaches the target refund. 0 when even no extra
+    /// withholding gives at least the target.
+    pub recommended_extra_withholding: f64,
+}
+
+impl W4Analysis {
+    /// The expected federal refund with the W-4 as given, to the cent. Negative when money is
+    /// owed instead.
+    pub fn projected_refund(&self) -> f64 {
+        ((self.annual_withholding - self.federal_tax) * 100.0).round() / 100.0
+    }
+}
+
+impl fmt::Display for W4Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Withholding Per Paycheck ({}): {}",
+            self.pay_frequency, self.withholding_per_paycheck
+        )?;
+        writeln!(
+            f,
+            "Supplemental Wage Withholding: {}",
+            self.supplemental_withholding
+        )?;
+        writeln!(f, "Annual Withholding: {}", self.annual_withholding)?;
+        writeln!(f, "Federal Taxes: {}", self.federal_tax)?;
+        let refund = self.projected_refund();
+        if refund >= 0.0 {
+            writeln!(f, "Projected Refund: {refund}")?;
+        } else {
+            writeln!(f, "Projected Amount Owed: {}", -refund)?;
+        }
+        write!(
+            f,
+            "Recommended Step 4(c) Extra Withholding: {} per paycheck (for a refund of {})",
+            self.recommended_extra_withholding, self.target_refund
+        )
+    }
+}
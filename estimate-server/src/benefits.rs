@@ -0,0 +1,183 @@
+/// Calculates what an employer pays on top of wages, i.e. the 401(k) match, and the total
+/// compensation it adds up to.
+use estimate_common::{
+    benefits::{CompensationResults, MatchTier},
+    common::TaxInfo,
+    errors::{EstimaterErrors, EstimaterResult},
+};
+
+use crate::utils::round_to_hundredths;
+
+/// Calculates the wages and employer benefits of the given input.
+///
+/// The match is on the typed 401(k) deferrals and the Roth deferrals as a share of the matched
+/// pay. The untyped pre-tax deductions are not matched.
+///
+/// # Params
+/// * `net_income` - The net income after taxes
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when a match tier or benefit is negative
+/// * `Ok(CompensationResults)`: The wages and benefits
+pub(crate) fn calculate_compensation_results(
+    input_info: &TaxInfo,
+    net_income: f64,
+) -> EstimaterResult<CompensationResults> {
+    let benefits = &input_info.benefits;
+    if benefits.hsa_contribution < 0.0
+        || benefits
+            .other_benefits
+            .iter()
+            .any(|benefit| benefit.value < 0.0)
+    {
+        return Err(EstimaterErrors::UserError(
+            "Employer benefits can not be negative".to_string(),
+        ));
+    }
+
+    Ok(CompensationResults {
+        wages: input_info.total_wages(),
+        employer_match: calculate_employer_match(
+            &benefits.match_tiers,
+            matched_pay(input_info),
+            input_info.elective_deferrals(),
+        )?,
+        employer_hsa: benefits.hsa_contribution,
+        other_benefits: benefits.other_benefits_value(),
+        net_income,
+    })
+}
+
+/// The pay the employer matches deferrals from and that limits annual additions: the salary,
+/// hourly and bonus pay. Equity and the wages of other employments are not part of it.
+pub(crate) fn matched_pay(input_info: &TaxInfo) -> f64 {
+    input_info.regular_wages() + input_info.bonus_income
+}
+
+/// Calculates the employer's match on elective deferrals.
+///
+/// Each tier matches the deferrals within the next slice of pay, i.e. with tiers of 100% up to
+/// 4% and 50% of the next 2%, deferring 5% of pay is matched 4% + 0.5%.
+///
+/// # Params
+/// * `match_tiers` - The tiers of the match formula, in order
+/// * `pay` - The pay the match is on
+/// * `deferrals` - The elective deferrals over the year
+pub(crate) fn calculate_employer_match(
+    match_tiers: &[MatchTier],
+    pay: f64,
+    deferrals: f64,
+) -> EstimaterResult<f64> {
+    if let Some(tier) = match_tiers
+        .iter()
+        .find(|tier| tier.match_percent < 0.0 || tier.of_pay_percent < 0.0)
+    {
+        return Err(EstimaterErrors::UserError(format!(
+            "The match tier of {}% up to {}% of pay can not be negative",
+            tier.match_percent, tier.of_pay_percent
+        )));
+    }
+
+    let mut unmatched_deferrals = deferrals.min(pay).max(0.0);
+    let mut employer_match = 0.0;
+    for tier in match_tiers.iter() {
+        let matched = unmatched_deferrals.min(pay * tier.of_pay_percent / 100.0);
+        employer_match += matched * tier.match_percent / 100.0;
+        unmatched_deferrals -= matched;
+    }
+    Ok(round_to_hundredths(employer_match))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use estimate_common::{
+        benefits::{EmployerBenefits, OtherBenefit},
+        deductions::{DeductionType, PreTaxDeduction},
+    };
+
+    fn help_make_tiers() -> Vec<MatchTier> {
+        vec![
+            MatchTier {
+                match_percent: 100.0,
+                of_pay_percent: 4.0,
+            },
+            MatchTier {
+                match_percent: 50.0,
+                of_pay_percent: 2.0,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_employer_match() {
+        let tiers = help_make_tiers();
+        // 3% of pay is all within the first tier
+        assert_eq!(
+            calculate_employer_match(&tiers, 100000.0, 3000.0).unwrap(),
+            3000.0
+        );
+        // 5% of pay is 4% matched fully and 1% matched half
+        assert_eq!(
+            calculate_employer_match(&tiers, 100000.0, 5000.0).unwrap(),
+            4500.0
+        );
+        // Deferring past the last tier earns nothing more
+        assert_eq!(
+            calculate_employer_match(&tiers, 100000.0, 22500.0).unwrap(),
+            5000.0
+        );
+        assert_eq!(
+            calculate_employer_match(&[], 100000.0, 5000.0).unwrap(),
+            0.0
+        );
+
+        let negative = [MatchTier {
+            match_percent: -50.0,
+            of_pay_percent: 6.0,
+        }];
+        assert!(calculate_employer_match(&negative, 100000.0, 5000.0).is_err());
+    }
+
+    #[test]
+    fn test_compensation_results() {
+        let info = TaxInfo {
+            gross_yearly_income: 90000,
+            bonus_income: 10000.0,
+            pre_tax_deducations: 2000.0,
+            deductions: vec![
+                PreTaxDeduction::new(DeductionType::Traditional401k, 3000.0),
+                PreTaxDeduction::new(DeductionType::Hsa, 2000.0),
+            ],
+            benefits: EmployerBenefits {
+                match_tiers: help_make_tiers(),
+                hsa_contribution: 1000.0,
+                other_benefits: vec![OtherBenefit {
+                    name: "Health Insurance".to_string(),
+                    value: 6000.0,
+                }],
+            },
+            ..Default::default()
+        };
+        let results =
+            calculate_compensation_results(&info, 70000.0).expect("Results should've calculated");
+
+        // Neither the HSA deduction nor the untyped deductions are deferrals, so 3% of the
+        // 100000 of pay is deferred
+        assert_eq!(results.employer_match, 3000.0);
+        assert_eq!(results.total_benefits(), 10000.0);
+        assert_eq!(results.total_compensation(), 110000.0);
+        assert_eq!(results.after_tax_compensation(), 80000.0);
+
+        // Roth deferrals are matched like pre-tax ones
+        let info = TaxInfo {
+            roth_deferrals: 2000.0,
+            ..info
+        };
+        let results =
+            calculate_compensation_results(&info, 70000.0).expect("Results should've calculated");
+        assert_eq!(results.employer_match, 4500.0);
+    }
+}
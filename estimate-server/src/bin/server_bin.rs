@@ -1,6 +1,86 @@
 /// Binary file to test & use server specific code as a binary without a client.
+use clap::{Args, Parser, Subcommand};
+use std::{path::PathBuf, process::ExitCode};
 
-// use estimate_server;
-fn main() {
-    println!("Hello world");
+use estimate_server::bracket_validation;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct ServerCli {
+    #[clap(subcommand)]
+    command: ServerCommands,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum ServerCommands {
+    /// Check a tax bracket file, reporting every problem found
+    Validate(ValidateStruct),
+    /// Rewrite a tax bracket file with every cumulative_previous_tax computed. Files with
+    /// comments or in the bracket_min / bracket_max format are refused.
+    FillCumulative(FillCumulativeStruct),
+}
+
+#[derive(Args, Clone, Debug)]
+struct ValidateStruct {
+    /// Path to the bracket file (json, toml or yaml) to check
+    path_to_file: PathBuf,
+}
+
+#[derive(Args, Clone, Debug)]
+struct FillCumulativeStruct {
+    /// Path to the bracket file (json, toml or yaml) to rewrite
+    path_to_file: PathBuf,
+    /// Where to write the rewritten file, in the format of its extension. Overwrites the input
+    /// file when not given.
+    #[clap(long = "output")]
+    output_path: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let args = ServerCli::parse();
+    match args.command {
+        ServerCommands::Validate(validate_struct) => validate(&validate_struct.path_to_file),
+        ServerCommands::FillCumulative(fill_struct) => fill_cumulative(
+            &fill_struct.path_to_file,
+            fill_struct
+                .output_path
+                .as_ref()
+                .unwrap_or(&fill_struct.path_to_file),
+        ),
+    }
+}
+
+/// Rewrites a bracket file with its cumulative previous taxes filled in.
+fn fill_cumulative(path_to_file: &PathBuf, output_path: &PathBuf) -> ExitCode {
+    match bracket_validation::rewrite_bracket_file(path_to_file, output_path) {
+        Err(err) => {
+            println!("Error rewriting {:?}.\n Error: {}", path_to_file, err);
+            ExitCode::FAILURE
+        }
+        Ok(()) => {
+            println!("Wrote {:?}", output_path);
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+/// Prints every problem in a bracket file. Fails when there are any.
+fn validate(path_to_file: &PathBuf) -> ExitCode {
+    match bracket_validation::diagnose_bracket_file(path_to_file) {
+        Err(err) => {
+            println!("Error validating {:?}.\n Error: {}", path_to_file, err);
+            ExitCode::FAILURE
+        }
+        Ok(diagnostics) if diagnostics.is_empty() => {
+            println!("{:?} is valid", path_to_file);
+            ExitCode::SUCCESS
+        }
+        Ok(diagnostics) => {
+            println!("{:?} has {} problem(s):", path_to_file, diagnostics.len());
+            for diagnostic in diagnostics.iter() {
+                println!("  {diagnostic}");
+            }
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,559 @@
+/// Validates a tax bracket file, reporting every problem found instead of stopping at the first.
+/// Also rewrites bracket files with their cumulative previous taxes filled in.
+use serde_json::Value;
+use std::{fmt, fs, path::Path};
+
+use estimate_common::{
+    errors::{BracketErrors, EstimaterErrors, EstimaterResult},
+    file_format::{write_file, FileFormat},
+};
+
+use crate::tax_bracket::{cumulative_taxes_match, BracketInfo, TaxBrackets};
+
+/// A single problem found in a bracket file
+#[derive(Debug)]
+pub struct BracketDiagnostic {
+    /// The index of the bracket within the file. None when the problem is with the file itself.
+    pub bracket_index: Option<usize>,
+    /// The (line, column), starting at 1, where the bracket, or the problem, begins. Only known
+    /// for json files.
+    pub position: Option<(usize, usize)>,
+    pub error: BracketErrors,
+}
+
+impl fmt::Display for BracketDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.bracket_index {
+            Some(bracket_index) => write!(f, "bracket {bracket_index}")?,
+            None => write!(f, "file")?,
+        }
+        if let Some((line, column)) = self.position {
+            write!(f, " (line {line}, column {column})")?;
+        }
+        write!(f, ": {}", self.error.to_string().trim_end())
+    }
+}
+
+/// A successfully parsed bracket along with where it is in the file
+struct LocatedBracket {
+    index: usize,
+    position: Option<(usize, usize)>,
+    bracket: BracketInfo,
+}
+
+impl LocatedBracket {
+    fn diagnose(&self, error: BracketErrors) -> BracketDiagnostic {
+        BracketDiagnostic {
+            bracket_index: Some(self.index),
+            position: self.position,
+            error,
+        }
+    }
+}
+
+/// Checks a json, toml or yaml (by its extension) bracket file for every problem that would stop
+/// it from being used.
+///
+/// Checks for brackets that are not in the expected format, tax rates outside of [0, 1],
+/// negative thresholds, minimums that are not below their maximums, a lowest bracket that
+/// doesn't start at 0, overlaps and gaps between brackets, and cumulative previous taxes that do
+/// not match the brackets before them.
+///
+/// # Return
+///
+/// * `EstimaterErrors::FileError` when the file could not be read
+/// * `Ok(diagnostics)`: Every problem found. Empty when the file is valid.
+pub fn diagnose_bracket_file(path: &Path) -> EstimaterResult<Vec<BracketDiagnostic>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| EstimaterErrors::FileError(format!("Could not read {:?}: {err}", path)))?;
+    match FileFormat::from_path(path) {
+        FileFormat::Json => Ok(diagnose_bracket_str(&contents)),
+        format => Ok(match format.parse::<Value>(&contents) {
+            Ok(root) => diagnose_bracket_value(&root, &[]),
+            Err(err) => vec![BracketDiagnostic {
+                bracket_index: None,
+                position: None,
+                error: BracketErrors::FormatError(err_message(err)),
+            }],
+        }),
+    }
+}
+
+/// Checks the contents of a json bracket file. See `diagnose_bracket_file`.
+pub(crate) fn diagnose_bracket_str(contents: &str) -> Vec<BracketDiagnostic> {
+    match serde_json::from_str(contents) {
+        Ok(root) => diagnose_bracket_value(&root, &locate_bracket_elements(contents)),
+        Err(err) => vec![BracketDiagnostic {
+            bracket_index: None,
+            position: Some((err.line(), err.column())),
+            error: BracketErrors::FormatError(err.to_string()),
+        }],
+    }
+}
+
+/// Checks the parsed contents of a bracket file.
+///
+/// # Params
+/// * `root` - The parsed bracket file
+/// * `positions` - Where each bracket starts in the file, if known
+fn diagnose_bracket_value(root: &Value, positions: &[(usize, usize)]) -> Vec<BracketDiagnostic> {
+    let Some(raw_brackets) = root.get("brackets").and_then(Value::as_array) else {
+        return vec![BracketDiagnostic {
+            bracket_index: None,
+            position: None,
+            error: BracketErrors::FormatError(
+                "The file does not contain a \"brackets\" list".to_string(),
+            ),
+        }];
+    };
+
+    let mut diagnostics = Vec::new();
+    let mut located_brackets = Vec::new();
+
+    for (index, raw_bracket) in raw_brackets.iter().enumerate() {
+        let position = positions.get(index).copied();
+        match serde_json::from_value::<BracketInfo>(raw_bracket.clone()) {
+            Ok(bracket) => located_brackets.push(LocatedBracket {
+                index,
+                position,
+                bracket,
+            }),
+            Err(err) => diagnostics.push(BracketDiagnostic {
+                bracket_index: Some(index),
+                position,
+                error: BracketErrors::FormatError(err.to_string()),
+            }),
+        }
+    }
+
+    for located in located_brackets.iter() {
+        if let Err(err) = located.bracket.validate_tax_rate() {
+            diagnostics.push(located.diagnose(err));
+        }
+        if let Err(err) = located.bracket.validate_range() {
+            diagnostics.push(located.diagnose(err));
+        }
+    }
+
+    located_brackets.sort_by(|first, second| first.bracket.cmp(&second.bracket));
+    if let Some(lowest) = located_brackets.first() {
+        if let Err(err) = lowest.bracket.validate_lowest() {
+            diagnostics.push(lowest.diagnose(err));
+        }
+    }
+    for pair in located_brackets.windows(2) {
+        let (previous, current) = (&pair[0], &pair[1]);
+        if let Err(err) = current.bracket.validate_follows(&previous.bracket) {
+            diagnostics.push(current.diagnose(err));
+        }
+    }
+
+    // Brackets without a cumulative previous tax are given the expected one, so the brackets
+    // after them are checked against it
+    let mut previous: Option<BracketInfo> = None;
+    for located in located_brackets.iter() {
+        let mut bracket = located.bracket.clone();
+        if let Ok(expected) = bracket.calculate_prev_bracket_max(&previous) {
+            match bracket.cumulative_previous_tax {
+                Some(stated) if !cumulative_taxes_match(stated, expected) => {
+                    diagnostics.push(located.diagnose(BracketErrors::CumulativeTaxError(format!(
+                        "Expected cumulative_previous_tax of {expected}, found {stated}"
+                    ))));
+                }
+                Some(_) => {}
+                None => bracket.cumulative_previous_tax = Some(expected),
+            }
+        }
+        previous = Some(bracket);
+    }
+
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.position, diagnostic.bracket_index));
+    diagnostics
+}
+
+/// Gets the message of a parsing error without its description of the error type
+fn err_message(err: EstimaterErrors) -> String {
+    match err {
+        EstimaterErrors::ParsingError(msg) => msg,
+        err => err.to_string(),
+    }
+}
+
+/// Rewrites a bracket file with the cumulative previous tax of every bracket filled in.
+/// Both files may be json, toml or yaml (by their extension), so this also converts between them.
+/// The file is written from scratch, so files the rewrite would change beyond the cumulative
+/// taxes are refused instead: files with comments, and files in the older `bracket_min` /
+/// `bracket_max` format, which would be written with thresholds.
+///
+/// # Params
+/// * `path` - The bracket file to read
+/// * `output_path` - Where to write the rewritten file. May be the same as `path`.
+///
+/// # Return
+///
+/// * Error if the file doesn't exist, its brackets are invalid, or it is refused
+pub fn rewrite_bracket_file(path: &Path, output_path: &Path) -> EstimaterResult<()> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| EstimaterErrors::FileError(format!("Could not read {:?}: {err}", path)))?;
+    if FileFormat::from_path(path) != FileFormat::Json && has_comments(&contents) {
+        return Err(EstimaterErrors::UserError(format!(
+            "{:?} has comments, which rewriting would drop. Remove them, or fill in the \
+             cumulative taxes by hand",
+            path
+        )));
+    }
+    let mut brackets = TaxBrackets::read_bracket_file(path)?;
+    if brackets.has_legacy_brackets() {
+        return Err(EstimaterErrors::UserError(format!(
+            "{:?} uses the older bracket_min / bracket_max format, which rewriting would \
+             replace with thresholds. Convert it to thresholds first",
+            path
+        )));
+    }
+    brackets.sort_brackets();
+    brackets.fill_cumulative_taxes()?;
+    brackets.validate_all_brackets()?;
+    write_file(output_path, &brackets)?;
+    // Loaded the way the estimator loads bracket files, to make sure the rewrite is usable
+    TaxBrackets::from_bracket_file(output_path.to_path_buf()).map(|_| ())
+}
+
+/// Whether a toml or yaml file has a `#` comment outside of a quoted string
+fn has_comments(contents: &str) -> bool {
+    contents.lines().any(|line| {
+        let mut quote: Option<char> = None;
+        for character in line.chars() {
+            match (quote, character) {
+                (None, '#') => return true,
+                (None, '"' | '\'') => quote = Some(character),
+                (Some(open), _) if open == character => quote = None,
+                _ => {}
+            }
+        }
+        false
+    })
+}
+
+/// Finds the line and column where each element of the top level "brackets" list starts.
+fn locate_bracket_elements(contents: &str) -> Vec<(usize, usize)> {
+    let mut positions = Vec::new();
+    let (mut line, mut column) = (1, 0);
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut current_string = String::new();
+    let mut last_key = String::new();
+    let mut brackets_depth: Option<usize> = None;
+    let mut expecting_element = false;
+
+    for character in contents.chars() {
+        if character == '\n' {
+            line += 1;
+            column = 0;
+        } else {
+            column += 1;
+        }
+
+        if in_string {
+            if escaped {
+                escaped = false;
+                current_string.push(character);
+            } else if character == '\\' {
+                escaped = true;
+            } else if character == '"' {
+                in_string = false;
+                if depth == 1 {
+                    last_key = std::mem::take(&mut current_string);
+                }
+            } else {
+                current_string.push(character);
+            }
+            continue;
+        }
+
+        if expecting_element && !character.is_whitespace() && character != ']' {
+            positions.push((line, column));
+            expecting_element = false;
+        }
+        match character {
+            '"' => {
+                in_string = true;
+                current_string.clear();
+            }
+            '{' | '[' => {
+                depth += 1;
+                if character == '['
+                    && depth == 2
+                    && last_key == "brackets"
+                    && brackets_depth.is_none()
+                {
+                    brackets_depth = Some(depth);
+                    expecting_element = true;
+                }
+            }
+            '}' | ']' => {
+                if brackets_depth == Some(depth) {
+                    expecting_element = false;
+                }
+                depth = depth.saturating_sub(1);
+            }
+            ',' if brackets_depth == Some(depth) => expecting_element = true,
+            _ => {}
+        }
+    }
+
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::get_path_to_shipped_data;
+
+    #[test]
+    fn test_shipped_brackets_are_valid() {
+        for file_name in [
+            "federal_tax_bracket.json",
+            "federal_tax_bracket_married_jointly.json",
+            "federal_tax_bracket_married_separately.json",
+        ] {
+            let diagnostics = diagnose_bracket_file(&get_path_to_shipped_data(file_name))
+                .expect("The file should be readable");
+            assert!(
+                diagnostics.is_empty(),
+                "{file_name} should be valid, but has {:?}",
+                diagnostics
+            );
+        }
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let bracket_json_str = r#"{
+  "brackets": [
+    {"bracket_min": 1, "bracket_max": 10275, "cumulative_previous_tax": 0.0, "tax_rate": 0.1},
+    {"bracket_min": 10000, "bracket_max": 41775, "cumulative_previous_tax": 500.0, "tax_rate": 1.2},
+    {"bracket_min": 50000, "bracket_max": 40000, "cumulative_previous_tax": 0.0, "tax_rate": 0.22},
+    {"bracket_min": "60000", "bracket_max": 70000, "cumulative_previous_tax": 0.0, "tax_rate": 0.22}
+  ]
+}"#;
+        let diagnostics = diagnose_bracket_str(bracket_json_str);
+        let found: Vec<(Option<usize>, usize, usize, &str)> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let (line, column) = diagnostic.position.unwrap_or_default();
+                let kind = match diagnostic.error {
+                    BracketErrors::TaxRateError(_) => "rate",
+                    BracketErrors::RangeError(_) => "range",
+                    BracketErrors::OverlapError(_) => "overlap",
+                    BracketErrors::GapError(_) => "gap",
+                    BracketErrors::CumulativeTaxError(_) => "cumulative",
+                    BracketErrors::FormatError(_) => "format",
+                    _ => "other",
+                };
+                (diagnostic.bracket_index, line, column, kind)
+            })
+            .collect();
+
+        assert!(found.contains(&(Some(1), 4, 5, "rate")), "{:?}", found);
+        assert!(found.contains(&(Some(1), 4, 5, "overlap")), "{:?}", found);
+        assert!(
+            found.contains(&(Some(1), 4, 5, "cumulative")),
+            "{:?}",
+            found
+        );
+        assert!(found.contains(&(Some(2), 5, 5, "range")), "{:?}", found);
+        assert!(found.contains(&(Some(2), 5, 5, "gap")), "{:?}", found);
+        assert!(found.contains(&(Some(3), 6, 5, "format")), "{:?}", found);
+    }
+
+    #[test]
+    fn test_reports_invalid_json_position() {
+        let diagnostics = diagnose_bracket_str("{\n  \"brackets\": [\n    {,\n  ]\n}");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].bracket_index, None);
+        assert_eq!(diagnostics[0].position.map(|(line, _)| line), Some(3));
+    }
+
+    #[test]
+    fn test_cumulative_taxes_within_rounding_are_valid() {
+        let bracket_json_str = r#"{
+  "brackets": [
+    {"threshold": 0, "tax_rate": 0.1, "cumulative_previous_tax": 0.0},
+    {"threshold": 1, "tax_rate": 0.2, "cumulative_previous_tax": 0.1},
+    {"threshold": 2, "tax_rate": 0.3, "cumulative_previous_tax": 0.3}
+  ]
+}"#;
+        let diagnostics = diagnose_bracket_str(bracket_json_str);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_missing_cumulative_taxes_are_valid() {
+        let bracket_json_str = r#"{
+  "brackets": [
+    {"bracket_min": 1, "bracket_max": 10275, "tax_rate": 0.1},
+    {"bracket_min": 10276, "bracket_max": 41775, "tax_rate": 0.12},
+    {"bracket_min": 41776, "bracket_max": 89075, "cumulative_previous_tax": 4807.5, "tax_rate": 0.22}
+  ]
+}"#;
+        let diagnostics = diagnose_bracket_str(bracket_json_str);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_rewrite_bracket_file() {
+        let output_path = std::env::temp_dir().join(format!(
+            "estimate_rewritten_brackets_{}.json",
+            std::process::id()
+        ));
+        rewrite_bracket_file(
+            &get_path_to_shipped_data("federal_tax_bracket.json"),
+            &output_path,
+        )
+        .expect("Rewriting the shipped brackets should've worked");
+
+        let rewritten = fs::read_to_string(&output_path).unwrap();
+        let rewritten_brackets = TaxBrackets::from_bracket_file(output_path.clone());
+        fs::remove_file(&output_path).unwrap();
+        let original_brackets =
+            TaxBrackets::from_bracket_file(get_path_to_shipped_data("federal_tax_bracket.json"))
+                .unwrap();
+        assert!(diagnose_bracket_str(&rewritten).is_empty());
+        assert!(
+            rewritten_brackets.unwrap().to_string() == original_brackets.to_string(),
+            "Rewriting already filled in brackets should not change them"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_refuses_legacy_brackets() {
+        let path = std::env::temp_dir().join(format!(
+            "estimate_legacy_brackets_{}.json",
+            std::process::id()
+        ));
+        let bracket_json_str = r#"{
+  "brackets": [
+    {"bracket_min": 1, "bracket_max": 10275, "tax_rate": 0.1},
+    {"bracket_min": 10276, "bracket_max": 41775, "tax_rate": 0.12}
+  ]
+}"#;
+        fs::write(&path, bracket_json_str).unwrap();
+
+        let result = rewrite_bracket_file(&path, &path);
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(
+            matches!(result, Err(EstimaterErrors::UserError(_))),
+            "Legacy brackets should be refused, got {:?}",
+            result
+        );
+        assert_eq!(
+            contents, bracket_json_str,
+            "A refused file should be untouched"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_refuses_comments() {
+        let path = std::env::temp_dir().join(format!(
+            "estimate_commented_brackets_{}.toml",
+            std::process::id()
+        ));
+        let bracket_toml_str = r#"# 2022 single filer brackets
+[[brackets]]
+threshold = 0.0
+tax_rate = 0.1
+
+[[brackets]]
+threshold = 10275.0 # the 12% bracket
+tax_rate = 0.12
+"#;
+        fs::write(&path, bracket_toml_str).unwrap();
+
+        let result = rewrite_bracket_file(&path, &path);
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(
+            matches!(result, Err(EstimaterErrors::UserError(_))),
+            "A file with comments should be refused, got {:?}",
+            result
+        );
+        assert_eq!(
+            contents, bracket_toml_str,
+            "A refused file should be untouched"
+        );
+        assert!(!has_comments("[[brackets]]\nname = \"#1\"\n"));
+    }
+
+    #[test]
+    fn test_reports_shared_threshold() {
+        let bracket_json_str = r#"{
+  "brackets": [
+    {"threshold": 0, "tax_rate": 0.1},
+    {"threshold": 10275, "tax_rate": 0.12},
+    {"threshold": 10275, "tax_rate": 0.22}
+  ]
+}"#;
+        let diagnostics = diagnose_bracket_str(bracket_json_str);
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert!(matches!(
+            diagnostics[0].error,
+            BracketErrors::OverlapError(_)
+        ));
+    }
+
+    #[test]
+    fn test_toml_and_yaml_bracket_files() {
+        let original_brackets =
+            TaxBrackets::from_bracket_file(get_path_to_shipped_data("federal_tax_bracket.json"))
+                .unwrap();
+        for extension in ["toml", "yaml"] {
+            let output_path = std::env::temp_dir().join(format!(
+                "estimate_converted_brackets_{}.{extension}",
+                std::process::id()
+            ));
+            rewrite_bracket_file(
+                &get_path_to_shipped_data("federal_tax_bracket.json"),
+                &output_path,
+            )
+            .unwrap_or_else(|err| panic!("Converting the brackets to {extension} failed: {err}"));
+
+            let diagnostics = diagnose_bracket_file(&output_path).unwrap();
+            let converted_brackets = TaxBrackets::from_bracket_file(output_path.clone());
+            fs::remove_file(&output_path).unwrap();
+            assert!(diagnostics.is_empty(), "{extension}: {:?}", diagnostics);
+            assert!(
+                converted_brackets.unwrap().to_string() == original_brackets.to_string(),
+                "The {extension} brackets should match the json ones"
+            );
+        }
+    }
+
+    #[test]
+    fn test_reports_invalid_toml() {
+        let path = std::env::temp_dir().join(format!(
+            "estimate_invalid_brackets_{}.toml",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            "# A comment\n[[brackets]]\nthreshold = 0\ntax_rate = 1.5\n\n[[brackets]]\ntax_rate = 0.1\n",
+        )
+        .unwrap();
+        let diagnostics = diagnose_bracket_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let kinds: Vec<(Option<usize>, bool)> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.bracket_index,
+                    matches!(diagnostic.error, BracketErrors::FormatError(_)),
+                )
+            })
+            .collect();
+        assert_eq!(kinds, vec![(Some(0), false), (Some(1), true)]);
+    }
+}
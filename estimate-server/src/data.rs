@@ -0,0 +1,247 @@
+/// Finds the data files (tax brackets, interest rates, ...) used by the server.
+///
+/// Each file is looked up, in order, in:
+/// 1. The directory set with `set_data_dir` (i.e. the client's `--data-dir` flag)
+/// 2. The directory named by the `ESTIMATE_DATA_DIR` environment variable
+/// 3. `$XDG_CONFIG_HOME/estimate` (or `~/.config/estimate`)
+/// 4. The copy of the shipped `data/` directory embedded at compile time
+///
+/// A directory only needs to contain the files it overrides. A file may be json, toml or yaml,
+/// i.e. `federal_tax_bracket.toml` overrides `federal_tax_bracket.json`.
+use serde::de::DeserializeOwned;
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use estimate_common::{
+    errors::{EstimaterErrors, EstimaterResult},
+    file_format::{read_file, FileFormat},
+};
+
+/// The environment variable naming a directory of data files
+pub const DATA_DIR_ENV_VAR: &str = "ESTIMATE_DATA_DIR";
+/// The directory within the XDG config directory holding data files
+const CONFIG_DIR_NAME: &str = "estimate";
+/// The extensions a data file may have, in order of preference
+const DATA_FILE_EXTENSIONS: [&str; 4] = ["json", "toml", "yaml", "yml"];
+
+/// The shipped data files, embedded so the binaries work from any directory
+const EMBEDDED_DATA: [(&str, &str); 7] = [
+    (
+        "federal_tax_bracket.json",
+        include_str!("../../data/federal_tax_bracket.json"),
+    ),
+    (
+        "federal_tax_bracket_married_jointly.json",
+        include_str!("../../data/federal_tax_bracket_married_jointly.json"),
+    ),
+    (
+        "federal_tax_bracket_married_separately.json",
+        include_str!("../../data/federal_tax_bracket_married_separately.json"),
+    ),
+    (
+        "underpayment_interest_rates.json",
+        include_str!("../../data/underpayment_interest_rates.json"),
+    ),
+    (
+        "social_security_wage_bases.json",
+        include_str!("../../data/social_security_wage_bases.json"),
+    ),
+    (
+        "standard_deductions.json",
+        include_str!("../../data/standard_deductions.json"),
+    ),
+    (
+        "retirement_plan_limits.json",
+        include_str!("../../data/retirement_plan_limits.json"),
+    ),
+];
+
+/// The directory given by the user, which takes precedence over every other one
+static DATA_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Sets the directory searched first for data files. `None` clears it.
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when the directory does not exist
+pub fn set_data_dir(data_dir: Option<PathBuf>) -> EstimaterResult<()> {
+    if let Some(data_dir) = &data_dir {
+        if !data_dir.is_dir() {
+            return Err(EstimaterErrors::UserError(format!(
+                "The data directory {:?} does not exist",
+                data_dir
+            )));
+        }
+    }
+    *DATA_DIR
+        .write()
+        .map_err(|err| EstimaterErrors::ServerError(err.to_string()))? = data_dir;
+    Ok(())
+}
+
+/// Reads a data file into the given struct from the first place it is found.
+///
+/// # Params
+/// * `file_name` - The name of the shipped file, i.e. `federal_tax_bracket.json`
+///
+/// # Return
+///
+/// * `EstimaterErrors::FileError` when the file is not found anywhere
+/// * `EstimaterErrors::ParsingError` when the file found is not in the expected format
+pub(crate) fn read_data_file<T: DeserializeOwned>(file_name: &str) -> EstimaterResult<T> {
+    if let Some(path) = find_data_file(file_name, &get_data_dirs()?) {
+        return read_file(&path);
+    }
+    let (_, contents) = EMBEDDED_DATA
+        .iter()
+        .find(|(embedded_name, _)| *embedded_name == file_name)
+        .ok_or_else(|| {
+            EstimaterErrors::FileError(format!("The data file {file_name} does not exist"))
+        })?;
+    FileFormat::from_path(Path::new(file_name)).parse(contents)
+}
+
+/// Gets the directories to search for data files, in order
+fn get_data_dirs() -> EstimaterResult<Vec<PathBuf>> {
+    let override_dir = DATA_DIR
+        .read()
+        .map_err(|err| EstimaterErrors::ServerError(err.to_string()))?
+        .clone();
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    Ok(layer_data_dirs(
+        override_dir,
+        env::var_os(DATA_DIR_ENV_VAR).map(PathBuf::from),
+        config_dir,
+    ))
+}
+
+/// Orders the directories to search for data files, skipping those that are not set.
+///
+/// # Params
+/// * `override_dir` - The directory set with `set_data_dir`
+/// * `env_dir` - The value of the `ESTIMATE_DATA_DIR` environment variable
+/// * `config_dir` - The XDG config directory, which holds the `estimate` directory
+fn layer_data_dirs(
+    override_dir: Option<PathBuf>,
+    env_dir: Option<PathBuf>,
+    config_dir: Option<PathBuf>,
+) -> Vec<PathBuf> {
+    [
+        override_dir,
+        env_dir.filter(|dir| !dir.as_os_str().is_empty()),
+        config_dir.map(|config_dir| config_dir.join(CONFIG_DIR_NAME)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Finds the first directory containing the data file, in any of the supported formats
+fn find_data_file(file_name: &str, data_dirs: &[PathBuf]) -> Option<PathBuf> {
+    let stem = Path::new(file_name).file_stem()?;
+    data_dirs.iter().find_map(|data_dir| {
+        DATA_FILE_EXTENSIONS
+            .iter()
+            .map(|extension| data_dir.join(stem).with_extension(extension))
+            .find(|path| path.is_file())
+    })
+}
+
+/// Gets the path to a file in the shipped `data/` directory
+#[cfg(test)]
+pub(crate) fn get_path_to_shipped_data(file_name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("data")
+        .join(file_name)
+}
+
+/// Searches the shipped `data/` directory first, so that tests do not depend on the data
+/// directories of the machine running them
+#[cfg(test)]
+pub(crate) fn use_shipped_data() {
+    set_data_dir(Some(get_path_to_shipped_data(""))).expect("The shipped data should exist");
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_every_embedded_file_parses() {
+        for (file_name, contents) in EMBEDDED_DATA.iter() {
+            let data =
+                FileFormat::from_path(Path::new(file_name)).parse::<serde_json::Value>(contents);
+            assert!(
+                data.is_ok(),
+                "{file_name} should parse, but {:?}",
+                data.err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_layer_data_dirs_in_order() {
+        let (override_dir, env_dir, config_dir) = (
+            PathBuf::from("override"),
+            PathBuf::from("env"),
+            PathBuf::from("config"),
+        );
+        assert_eq!(
+            layer_data_dirs(
+                Some(override_dir.clone()),
+                Some(env_dir.clone()),
+                Some(config_dir.clone())
+            ),
+            vec![
+                override_dir.clone(),
+                env_dir.clone(),
+                config_dir.join(CONFIG_DIR_NAME)
+            ]
+        );
+        assert_eq!(
+            layer_data_dirs(None, Some(env_dir.clone()), None),
+            vec![env_dir]
+        );
+        // An empty environment variable is the same as an unset one
+        assert_eq!(
+            layer_data_dirs(Some(override_dir.clone()), Some(PathBuf::new()), None),
+            vec![override_dir]
+        );
+        assert!(layer_data_dirs(None, None, None).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_data_file() {
+        assert!(read_data_file::<serde_json::Value>("no_such_file.json").is_err());
+    }
+
+    #[test]
+    fn test_find_data_file_in_order() {
+        let root = env::temp_dir().join(format!("estimate_data_dirs_{}", std::process::id()));
+        let (first, second) = (root.join("first"), root.join("second"));
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        fs::write(second.join("rates.json"), "{}").unwrap();
+        fs::write(second.join("brackets.toml"), "").unwrap();
+        fs::write(first.join("brackets.yaml"), "").unwrap();
+
+        let data_dirs = vec![first.clone(), second.clone()];
+        let found_rates = find_data_file("rates.json", &data_dirs);
+        let found_brackets = find_data_file("brackets.json", &data_dirs);
+        let found_missing = find_data_file("missing.json", &data_dirs);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(found_rates, Some(second.join("rates.json")));
+        assert_eq!(found_brackets, Some(first.join("brackets.yaml")));
+        assert_eq!(found_missing, None);
+    }
+}
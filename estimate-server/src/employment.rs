@@ -0,0 +1,169 @@
+/// Calculates the Social Security and Medicare tax withheld by each employer over a year, and
+/// the excess Social Security credit when more than one employer withheld it.
+use chrono::Datelike;
+
+use estimate_common::{
+    employment::{EmployerFica, Employment, EmploymentResults},
+    errors::{EstimaterErrors, EstimaterResult},
+};
+
+use crate::{
+    paycheck::{
+        WageBaseTable, ADDITIONAL_MEDICARE_RATE, ADDITIONAL_MEDICARE_THRESHOLD, MEDICARE_RATE,
+        SOCIAL_SECURITY_RATE,
+    },
+    utils::round_to_hundredths,
+};
+
+/// Calculates the Social Security and Medicare tax withheld by each employer.
+///
+/// Every employer withholds Social Security tax up to the wage base on its own wages, so
+/// someone with more than one employer can have more withheld than they owe. The excess is
+/// claimed back on the return. Only the amount each employer was required to withhold counts
+/// towards the excess.
+///
+/// The wage base is the one of the employments' year. The federal brackets carry no year, so
+/// nothing checks that they are of the same year (the shipped brackets are for 2022).
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when an employment ends before it starts, or the employments
+///   are not all within a single year with a known wage base
+/// * `Ok(EmploymentResults)`: The tax withheld by each employer and the excess credit
+pub(crate) fn calculate_employment_results(
+    employments: &[Employment],
+) -> EstimaterResult<EmploymentResults> {
+    let tax_year = get_tax_year(employments)?;
+    let wage_base = WageBaseTable::from_data_file()?.wage_base_for(tax_year)?;
+    let max_social_security = round_to_hundredths(wage_base * SOCIAL_SECURITY_RATE);
+
+    let employers: Vec<EmployerFica> = employments
+        .iter()
+        .map(|employment| EmployerFica {
+            employer: employment.employer.clone(),
+            wages: employment.wages,
+            social_security: employment.social_security_withheld.unwrap_or_else(|| {
+                round_to_hundredths(employment.wages.min(wage_base) * SOCIAL_SECURITY_RATE)
+            }),
+            medicare: employment.medicare_withheld.unwrap_or_else(|| {
+                round_to_hundredths(
+                    employment.wages * MEDICARE_RATE
+                        + (employment.wages - ADDITIONAL_MEDICARE_THRESHOLD).max(0.0)
+                            * ADDITIONAL_MEDICARE_RATE,
+                )
+            }),
+        })
+        .collect();
+
+    let excess_social_security = if employers.len() > 1 {
+        let creditable: f64 = employers
+            .iter()
+            .map(|employer| employer.social_security.min(max_social_security))
+            .sum();
+        round_to_hundredths((creditable - max_social_security).max(0.0))
+    } else {
+        0.0
+    };
+
+    Ok(EmploymentResults {
+        employers,
+        max_social_security,
+        excess_social_security,
+    })
+}
+
+/// Gets the year every employment falls within
+fn get_tax_year(employments: &[Employment]) -> EstimaterResult<i32> {
+    let first = employments
+        .first()
+        .ok_or_else(|| EstimaterErrors::UserError("No employments were given".to_string()))?;
+    let tax_year = first.start_date.year();
+    for employment in employments.iter() {
+        if employment.end_date < employment.start_date {
+            return Err(EstimaterErrors::UserError(format!(
+                "The employment at {} ends on {}, before it starts on {}",
+                employment.employer, employment.end_date, employment.start_date
+            )));
+        }
+        if employment.start_date.year() != tax_year || employment.end_date.year() != tax_year {
+            return Err(EstimaterErrors::UserError(format!(
+                "The employment at {} is not within {tax_year}, the year of the other \
+                 employments",
+                employment.employer
+            )));
+        }
+    }
+    Ok(tax_year)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::use_shipped_data;
+    use chrono::NaiveDate;
+
+    fn help_make_employment(employer: &str, start_month: u32, wages: f64) -> Employment {
+        Employment {
+            employer: employer.to_string(),
+            start_date: NaiveDate::from_ymd_opt(2023, start_month, 1).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2023, start_month + 5, 30).unwrap(),
+            wages,
+            federal_withholding: 0.0,
+            state_withholding: 0.0,
+            social_security_withheld: None,
+            medicare_withheld: None,
+        }
+    }
+
+    #[test]
+    fn test_excess_social_security() {
+        use_shipped_data();
+        let employments = vec![
+            help_make_employment("First", 1, 100000.0),
+            help_make_employment("Second", 7, 100000.0),
+        ];
+        let results =
+            calculate_employment_results(&employments).expect("Results should've calculated");
+
+        assert_eq!(results.employers[0].social_security, 6200.0);
+        assert_eq!(results.employers[1].medicare, 1450.0);
+        // 2023's wage base of 160200 caps the Social Security owed at 9932.4
+        assert_eq!(results.max_social_security, 9932.4);
+        assert_eq!(results.excess_social_security, 2467.6);
+    }
+
+    #[test]
+    fn test_single_employer_has_no_excess() {
+        use_shipped_data();
+        let mut employment = help_make_employment("Only", 1, 250000.0);
+        let results = calculate_employment_results(&[employment.clone()])
+            .expect("Results should've calculated");
+        assert_eq!(results.employers[0].social_security, 9932.4);
+        // The additional Medicare tax is withheld above 200000
+        assert_eq!(results.employers[0].medicare, 3625.0 + 450.0);
+        assert_eq!(results.excess_social_security, 0.0);
+
+        // An employer withholding too much must refund it, so it is not part of the credit
+        employment.social_security_withheld = Some(12000.0);
+        let results =
+            calculate_employment_results(&[employment, help_make_employment("Other", 7, 1000.0)])
+                .expect("Results should've calculated");
+        assert_eq!(results.excess_social_security, 62.0);
+    }
+
+    #[test]
+    fn test_invalid_employments() {
+        let mut backwards = help_make_employment("Backwards", 1, 1000.0);
+        backwards.end_date = NaiveDate::from_ymd_opt(2022, 12, 31).unwrap();
+        assert!(calculate_employment_results(&[backwards]).is_err());
+
+        let mut next_year = help_make_employment("Next Year", 7, 1000.0);
+        next_year.end_date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        assert!(calculate_employment_results(&[
+            help_make_employment("This Year", 1, 1000.0),
+            next_year
+        ])
+        .is_err());
+    }
+}
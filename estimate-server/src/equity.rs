@@ -0,0 +1,335 @@
+/// Calculates the taxes caused by equity compensation (i.e. RSUs, ESPPs and ISOs).
+use chrono::{Months, NaiveDate};
+
+use estimate_common::{
+    equity::{
+        DispositionResult, DispositionType, EquityDispositionResults, EsppSale, IsoExercise,
+        RsuResults, RsuVest, RsuVestResult,
+    },
+    errors::{EstimaterErrors, EstimaterResult},
+};
+
+use crate::{
+    supplemental::calculate_supplemental_withholding, tax_bracket::TaxBrackets,
+    utils::round_to_hundredths,
+};
+
+/// Calculates the sell-to-cover withholding of each vest and the federal tax all vests add.
+/// Vests are withheld in date order at the flat supplemental rates, after any bonus.
+///
+/// # Params
+/// * `brackets` - The federal brackets of the filer
+/// * `taxable_income` - The taxable income, including the vests
+/// * `bonus_income` - Supplemental wages paid before the vests
+/// * `rsu_vests` - The vests over the year
+pub(crate) fn calculate_rsu_results(
+    brackets: &TaxBrackets,
+    taxable_income: f64,
+    bonus_income: f64,
+    rsu_vests: &[RsuVest],
+) -> EstimaterResult<RsuResults> {
+    let mut sorted_vests = rsu_vests.to_vec();
+    sorted_vests.sort_by_key(|vest| vest.vest_date);
+
+    let mut supplemental_paid = bonus_income;
+    let mut rsu_income = 0.0;
+    let vests = sorted_vests
+        .iter()
+        .map(|vest| {
+            let income = vest.income();
+            let withheld = round_to_hundredths(
+                calculate_supplemental_withholding(supplemental_paid + income)
+                    - calculate_supplemental_withholding(supplemental_paid),
+            );
+            supplemental_paid += income;
+            rsu_income += income;
+            RsuVestResult {
+                vest_date: vest.vest_date,
+                income,
+                withheld,
+                shares_sold: calculate_shares_sold(withheld, vest.price),
+            }
+        })
+        .collect();
+
+    let tax_with_vests = brackets.calculate_tax_amount(taxable_income.max(0.0))?;
+    let tax_without_vests =
+        brackets.calculate_tax_amount((taxable_income - rsu_income).max(0.0))?;
+
+    Ok(RsuResults {
+        vests,
+        incremental_tax: round_to_hundredths(tax_with_vests - tax_without_vests),
+    })
+}
+
+/// The whole number of shares that must be sold to cover the withholding
+fn calculate_shares_sold(withheld: f64, price: f64) -> u64 {
+    if price <= 0.0 {
+        return 0;
+    }
+    (withheld / price).ceil() as u64
+}
+
+/// Splits every ESPP and ISO sale into ordinary income and capital gain, and totals the AMT
+/// preference of ISO shares held past the end of the year they were exercised.
+///
+/// An ISO exercise falls in the tax year when its shares are still held, as a sold exercise
+/// is in the year of its sale. So only unsold exercises add an AMT preference: shares sold in
+/// the year of exercise add none, and a sale after an earlier year's exercise reverses a
+/// preference from that earlier year instead.
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when an ISO exercise has only one of a sale date and price
+pub(crate) fn calculate_equity_dispositions(
+    espp_sales: &[EsppSale],
+    iso_exercises: &[IsoExercise],
+) -> EstimaterResult<EquityDispositionResults> {
+    let mut dispositions: Vec<DispositionResult> =
+        espp_sales.iter().map(calculate_espp_disposition).collect();
+    let mut amt_preference = 0.0;
+
+    for exercise in iso_exercises.iter() {
+        match (exercise.sale_date, exercise.sale_price) {
+            (Some(sale_date), Some(sale_price)) => {
+                dispositions.push(calculate_iso_disposition(exercise, sale_date, sale_price))
+            }
+            (None, None) => amt_preference += calculate_iso_bargain_element(exercise),
+            _ => {
+                return Err(EstimaterErrors::UserError(format!(
+                    "The ISO exercise on {} needs both a sale date and sale price, or neither",
+                    exercise.exercise_date
+                )))
+            }
+        }
+    }
+
+    Ok(EquityDispositionResults {
+        dispositions,
+        amt_preference: round_to_hundredths(amt_preference),
+    })
+}
+
+/// A sale of ESPP shares is qualifying when sold over 2 years after the offering date and over
+/// 1 year after the purchase.
+///
+/// * Qualifying: ordinary income is the lesser of the actual gain and the discount on the
+///   offering date price. The rest is long term capital gain.
+/// * Disqualifying: ordinary income is the discount on the purchase date price. The rest is
+///   capital gain (or loss).
+fn calculate_espp_disposition(sale: &EsppSale) -> DispositionResult {
+    let is_qualifying = is_held_over_years(sale.offering_date, sale.sale_date, 2)
+        && is_held_over_years(sale.purchase_date, sale.sale_date, 1);
+    let gain_per_share = sale.sale_price - sale.purchase_price;
+
+    let (disposition_type, ordinary_per_share) = if is_qualifying {
+        let offering_discount = sale.offering_date_price * sale.discount_rate;
+        (
+            DispositionType::Qualifying,
+            gain_per_share.min(offering_discount).max(0.0),
+        )
+    } else {
+        (
+            DispositionType::Disqualifying,
+            sale.purchase_date_price - sale.purchase_price,
+        )
+    };
+
+    DispositionResult {
+        plan: "ESPP".to_string(),
+        sale_date: sale.sale_date,
+        disposition_type,
+        ordinary_income: round_to_hundredths(ordinary_per_share * sale.shares),
+        capital_gain: round_to_hundredths((gain_per_share - ordinary_per_share) * sale.shares),
+        is_long_term: is_held_over_years(sale.purchase_date, sale.sale_date, 1),
+    }
+}
+
+/// A sale of ISO shares is qualifying when sold over 2 years after the grant and over 1 year
+/// after the exercise.
+///
+/// * Qualifying: the entire gain over the strike price is long term capital gain.
+/// * Disqualifying: ordinary income is the lesser of the bargain element at exercise and the
+///   actual gain. The rest is capital gain.
+fn calculate_iso_disposition(
+    exercise: &IsoExercise,
+    sale_date: NaiveDate,
+    sale_price: f64,
+) -> DispositionResult {
+    let is_qualifying = is_held_over_years(exercise.grant_date, sale_date, 2)
+        && is_held_over_years(exercise.exercise_date, sale_date, 1);
+    let gain_per_share = sale_price - exercise.strike_price;
+
+    let (disposition_type, ordinary_per_share) = if is_qualifying {
+        (DispositionType::Qualifying, 0.0)
+    } else {
+        let bargain_element = exercise.exercise_date_price - exercise.strike_price;
+        (
+            DispositionType::Disqualifying,
+            gain_per_share.min(bargain_element).max(0.0),
+        )
+    };
+
+    DispositionResult {
+        plan: "ISO".to_string(),
+        sale_date,
+        disposition_type,
+        ordinary_income: round_to_hundredths(ordinary_per_share * exercise.shares),
+        capital_gain: round_to_hundredths((gain_per_share - ordinary_per_share) * exercise.shares),
+        is_long_term: is_held_over_years(exercise.exercise_date, sale_date, 1),
+    }
+}
+
+/// The spread between the share price and strike price at exercise
+fn calculate_iso_bargain_element(exercise: &IsoExercise) -> f64 {
+    ((exercise.exercise_date_price - exercise.strike_price) * exercise.shares).max(0.0)
+}
+
+/// Whether shares were held for more than the given number of years
+fn is_held_over_years(start: NaiveDate, end: NaiveDate, years: u32) -> bool {
+    start
+        .checked_add_months(Months::new(12 * years))
+        .is_some_and(|anniversary| end > anniversary)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::get_path_to_shipped_data;
+
+    fn help_make_vest(month: u32, shares: u64, price: f64) -> RsuVest {
+        RsuVest {
+            vest_date: NaiveDate::from_ymd_opt(2022, month, 15).unwrap(),
+            shares,
+            price,
+        }
+    }
+
+    #[test]
+    fn test_calculate_rsu_results() {
+        let brackets =
+            TaxBrackets::from_bracket_file(get_path_to_shipped_data("federal_tax_bracket.json"))
+                .expect("Brackets should load");
+        let vests = vec![help_make_vest(8, 100, 100.0), help_make_vest(2, 100, 100.0)];
+
+        // 100000 of salary plus 20000 of vests puts all of the vests in the 24% bracket
+        let results = calculate_rsu_results(&brackets, 120000.0, 0.0, &vests)
+            .expect("Calculating RSUs should've worked");
+        assert_eq!(
+            results.vests[0].vest_date,
+            NaiveDate::from_ymd_opt(2022, 2, 15).unwrap()
+        );
+        assert_eq!(results.vests[0].withheld, 2200.0);
+        assert_eq!(results.vests[0].shares_sold, 22);
+        assert!(
+            results.incremental_tax == 4800.0,
+            "Expected: 4800.0. Got: {}",
+            results.incremental_tax
+        );
+        assert!(
+            results.withholding_gap() == -400.0,
+            "Expected: -400.0. Got: {}",
+            results.withholding_gap()
+        );
+    }
+
+    #[test]
+    fn test_vests_after_a_million_use_mandatory_rate() {
+        let brackets =
+            TaxBrackets::from_bracket_file(get_path_to_shipped_data("federal_tax_bracket.json"))
+                .expect("Brackets should load");
+        let vests = vec![help_make_vest(3, 1000, 100.0)];
+
+        let results = calculate_rsu_results(&brackets, 1_200_000.0, 1_000_000.0, &vests)
+            .expect("Calculating RSUs should've worked");
+        assert_eq!(results.vests[0].withheld, 37000.0);
+        assert_eq!(results.vests[0].shares_sold, 370);
+    }
+
+    fn help_date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn help_make_espp_sale(sale_date: NaiveDate) -> EsppSale {
+        EsppSale {
+            offering_date: help_date(2020, 1, 1),
+            purchase_date: help_date(2020, 6, 30),
+            sale_date,
+            shares: 100.0,
+            offering_date_price: 10.0,
+            purchase_date_price: 20.0,
+            purchase_price: 8.5,
+            sale_price: 25.0,
+            discount_rate: 0.15,
+        }
+    }
+
+    fn help_make_iso_exercise(sale: Option<(NaiveDate, f64)>) -> IsoExercise {
+        IsoExercise {
+            grant_date: help_date(2019, 1, 1),
+            exercise_date: help_date(2021, 1, 4),
+            shares: 100.0,
+            strike_price: 10.0,
+            exercise_date_price: 30.0,
+            sale_date: sale.map(|(date, _)| date),
+            sale_price: sale.map(|(_, price)| price),
+        }
+    }
+
+    #[test]
+    fn test_espp_dispositions() {
+        let qualifying = calculate_espp_disposition(&help_make_espp_sale(help_date(2022, 7, 1)));
+        assert_eq!(qualifying.disposition_type, DispositionType::Qualifying);
+        assert_eq!(qualifying.ordinary_income, 150.0);
+        assert_eq!(qualifying.capital_gain, 1500.0);
+        assert!(qualifying.is_long_term);
+
+        let disqualifying = calculate_espp_disposition(&help_make_espp_sale(help_date(2021, 1, 1)));
+        assert_eq!(
+            disqualifying.disposition_type,
+            DispositionType::Disqualifying
+        );
+        assert_eq!(disqualifying.ordinary_income, 1150.0);
+        assert_eq!(disqualifying.capital_gain, 500.0);
+        assert!(!disqualifying.is_long_term);
+    }
+
+    #[test]
+    fn test_iso_dispositions() {
+        let exercises = vec![
+            help_make_iso_exercise(None),
+            help_make_iso_exercise(Some((help_date(2021, 6, 1), 25.0))),
+            help_make_iso_exercise(Some((help_date(2022, 6, 1), 40.0))),
+        ];
+        let results = calculate_equity_dispositions(&[], &exercises)
+            .expect("Calculating dispositions should've worked");
+
+        // Only the unsold shares are exercised this year and held past its end. The shares sold
+        // in 2022 were exercised in an earlier tax year.
+        assert_eq!(results.amt_preference, 2000.0);
+
+        let disqualifying = &results.dispositions[0];
+        assert_eq!(
+            disqualifying.disposition_type,
+            DispositionType::Disqualifying
+        );
+        assert_eq!(disqualifying.ordinary_income, 1500.0);
+        assert_eq!(disqualifying.capital_gain, 0.0);
+
+        let qualifying = &results.dispositions[1];
+        assert_eq!(qualifying.disposition_type, DispositionType::Qualifying);
+        assert_eq!(qualifying.ordinary_income, 0.0);
+        assert_eq!(qualifying.capital_gain, 3000.0);
+    }
+
+    #[test]
+    fn test_iso_sale_needs_date_and_price() {
+        let mut exercise = help_make_iso_exercise(None);
+        exercise.sale_price = Some(40.0);
+        assert!(
+            calculate_equity_dispositions(&[], &[exercise]).is_err(),
+            "An ISO sale price without a date did not error as expected"
+        );
+    }
+}
@@ -0,0 +1,228 @@
+/// Plans quarterly estimated tax payments using the IRS safe harbor rules.
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+use estimate_common::{
+    common::{FilingStatus, TaxInfo},
+    errors::{EstimaterErrors, EstimaterResult},
+    estimated_payments::{EstimatedPayment, EstimatedPaymentPlan, SafeHarborInfo, SafeHarborRule},
+};
+
+use crate::{server::calculate_taxes_with_standard_deduction, utils::round_to_hundredths};
+
+/// When the amount owed after withholding is below this, no estimated payments are required.
+const MINIMUM_AMOUNT_OWED: f64 = 1000.0;
+/// Prior year AGI above which the prior year safe harbor rises to 110%.
+const HIGH_INCOME_AGI: f64 = 150000.0;
+/// Same as `HIGH_INCOME_AGI`, but for married filing separately.
+const HIGH_INCOME_AGI_SEPARATELY: f64 = 75000.0;
+
+/// Calculates the quarterly estimated payments needed to avoid an underpayment penalty.
+///
+/// # Params
+/// * `input_info` - The current year's tax info. Its tax is taken after the standard deduction.
+/// * `safe_harbor_info` - The prior year's tax and AGI, along with the current withholding
+///
+/// # Return
+///
+/// * `Error`: Some error explaining why the calculation could not be completed
+/// * `Ok(EstimatedPaymentPlan)`: The required annual payment and each installment
+pub fn plan_estimated_payments(
+    input_info: &TaxInfo,
+    safe_harbor_info: &SafeHarborInfo,
+) -> EstimaterResult<EstimatedPaymentPlan> {
+    let current_year_tax = calculate_taxes_with_standard_deduction(input_info)?.federal_tax;
+    let (safe_harbor_rule, required_annual_payment) = determine_required_annual_payment(
+        current_year_tax,
+        safe_harbor_info,
+        &input_info.filing_status,
+    );
+
+    let installment_amount = if current_year_tax - safe_harbor_info.withholding
+        < MINIMUM_AMOUNT_OWED
+    {
+        0.0
+    } else {
+        round_to_hundredths((required_annual_payment - safe_harbor_info.withholding).max(0.0) / 4.0)
+    };
+
+    let installments = get_installment_due_dates(safe_harbor_info.tax_year)?
+        .into_iter()
+        .map(|due_date| EstimatedPayment {
+            due_date,
+            amount: installment_amount,
+        })
+        .collect();
+
+    Ok(EstimatedPaymentPlan {
+        current_year_tax,
+        required_annual_payment,
+        safe_harbor_rule,
+        installments,
+    })
+}
+
+/// Determines the smallest total payment that still satisfies a safe harbor rule.
+///
+/// # Return
+///
+/// * The rule used and the required annual payment
+pub(crate) fn determine_required_annual_payment(
+    current_year_tax: f64,
+    safe_harbor_info: &SafeHarborInfo,
+    filing_status: &FilingStatus,
+) -> (SafeHarborRule, f64) {
+    let high_income_agi = match filing_status {
+        FilingStatus::MarriedFilingSeparately => HIGH_INCOME_AGI_SEPARATELY,
+        _ => HIGH_INCOME_AGI,
+    };
+    let (prior_year_rule, prior_year_required) =
+        if safe_harbor_info.prior_year_agi > high_income_agi {
+            (
+                SafeHarborRule::PriorYearHighIncome,
+                safe_harbor_info.prior_year_tax * 1.1,
+            )
+        } else {
+            (SafeHarborRule::PriorYear, safe_harbor_info.prior_year_tax)
+        };
+    let current_year_required = current_year_tax * 0.9;
+
+    if current_year_required < prior_year_required {
+        (
+            SafeHarborRule::CurrentYear,
+            round_to_hundredths(current_year_required),
+        )
+    } else {
+        (prior_year_rule, round_to_hundredths(prior_year_required))
+    }
+}
+
+/// Gets the four due dates of the estimated payments for a tax year.
+/// A due date falling on a weekend moves to the following Monday. Holidays are not considered.
+pub(crate) fn get_installment_due_dates(tax_year: i32) -> EstimaterResult<Vec<NaiveDate>> {
+    [
+        (tax_year, 4, 15),
+        (tax_year, 6, 15),
+        (tax_year, 9, 15),
+        (tax_year + 1, 1, 15),
+    ]
+    .into_iter()
+    .map(|(year, month, day)| {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .map(next_business_day)
+            .ok_or_else(|| EstimaterErrors::UserError(format!("Invalid tax year {tax_year}")))
+    })
+    .collect()
+}
+
+/// Moves a date on a weekend to the following Monday
+fn next_business_day(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date + Duration::days(2),
+        Weekday::Sun => date + Duration::days(1),
+        _ => date,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::use_shipped_data;
+
+    fn help_make_safe_harbor(prior_year_tax: f64, prior_year_agi: f64) -> SafeHarborInfo {
+        SafeHarborInfo {
+            tax_year: 2022,
+            prior_year_tax,
+            prior_year_agi,
+            withholding: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_determine_required_annual_payment() {
+        let status = FilingStatus::Single;
+        assert_eq!(
+            determine_required_annual_payment(
+                10000.0,
+                &help_make_safe_harbor(5000.0, 50000.0),
+                &status
+            ),
+            (SafeHarborRule::PriorYear, 5000.0)
+        );
+        assert_eq!(
+            determine_required_annual_payment(
+                10000.0,
+                &help_make_safe_harbor(5000.0, 200000.0),
+                &status
+            ),
+            (SafeHarborRule::PriorYearHighIncome, 5500.0)
+        );
+        assert_eq!(
+            determine_required_annual_payment(
+                10000.0,
+                &help_make_safe_harbor(20000.0, 50000.0),
+                &status
+            ),
+            (SafeHarborRule::CurrentYear, 9000.0)
+        );
+        assert_eq!(
+            determine_required_annual_payment(
+                10000.0,
+                &help_make_safe_harbor(5000.0, 100000.0),
+                &FilingStatus::MarriedFilingSeparately
+            ),
+            (SafeHarborRule::PriorYearHighIncome, 5500.0)
+        );
+    }
+
+    #[test]
+    fn test_get_installment_due_dates() {
+        let due_dates = get_installment_due_dates(2022).expect("Due dates should exist");
+        let expected = vec![
+            NaiveDate::from_ymd_opt(2022, 4, 15).unwrap(),
+            NaiveDate::from_ymd_opt(2022, 6, 15).unwrap(),
+            NaiveDate::from_ymd_opt(2022, 9, 15).unwrap(),
+            // January 15th 2023 is a Sunday
+            NaiveDate::from_ymd_opt(2023, 1, 16).unwrap(),
+        ];
+        assert_eq!(due_dates, expected);
+    }
+
+    #[test]
+    fn test_plan_estimated_payments() {
+        use_shipped_data();
+        let info = TaxInfo {
+            gross_yearly_income: 50000,
+            state_tax_rate_percent: 5.0,
+            ..Default::default()
+        };
+        let mut safe_harbor = help_make_safe_harbor(6000.0, 45000.0);
+        safe_harbor.withholding = 2000.0;
+
+        let plan = plan_estimated_payments(&info, &safe_harbor).expect("Plan should've worked");
+        // 1027.5 + 12% of (50000 - 12950 - 10275)
+        assert!(
+            plan.current_year_tax == 4240.5,
+            "Expected: 4240.5. Got: {}",
+            plan.current_year_tax
+        );
+        assert!(
+            plan.safe_harbor_rule == SafeHarborRule::CurrentYear,
+            "Expected the current year rule. Got: {}",
+            plan.safe_harbor_rule
+        );
+        assert!(
+            plan.installments[0].amount == 454.11,
+            "Expected: 454.11. Got: {}",
+            plan.installments[0].amount
+        );
+
+        safe_harbor.withholding = 6000.0;
+        let plan = plan_estimated_payments(&info, &safe_harbor).expect("Plan should've worked");
+        assert!(
+            plan.total_estimated_payments() == 0.0,
+            "Owing less than $1000 should not require payments. Got: {}",
+            plan.total_estimated_payments()
+        );
+    }
+}
@@ -1,2 +1,19 @@
+mod benefits;
+pub mod bracket_validation;
+pub mod data;
+mod employment;
+mod equity;
+pub mod estimated_payments;
+pub mod marriage;
+pub mod paycheck;
+pub mod projection;
+pub mod retirement;
+pub mod reverse;
+pub mod scenarios;
 pub mod server;
+mod standard_deduction;
+mod supplemental;
 mod tax_bracket;
+pub mod underpayment;
+mod utils;
+pub mod w4;
@@ -0,0 +1,245 @@
+/// Compares the taxes a couple pays when married against filing as two single people.
+use estimate_common::{
+    benefits::{CompensationResults, EmployerBenefits},
+    common::{FilingStatus, HourlyWage, MarriageAnalysis, TaxInfo},
+    employment::EmploymentResults,
+    errors::{EstimaterErrors, EstimaterResult},
+};
+
+use crate::{
+    benefits::calculate_compensation_results, employment::calculate_employment_results,
+    server::calculate_taxes_with_standard_deduction,
+};
+
+/// Calculates the taxes for two people filing as single, married filing jointly and married
+/// filing separately. Each return takes the brackets and standard deduction of its filing
+/// status.
+///
+/// # Params
+/// * `first` - The tax info of the first person. The filing status is ignored.
+/// * `second` - The tax info of the second person. The filing status is ignored.
+///
+/// # Return
+///
+/// * `Error`: Some error explaining why the calculation could not be completed
+/// * `Ok(MarriageAnalysis)`: The taxes paid under each filing status
+pub fn analyze_marriage(first: &TaxInfo, second: &TaxInfo) -> EstimaterResult<MarriageAnalysis> {
+    let first_single =
+        calculate_taxes_with_standard_deduction(&with_filing_status(first, FilingStatus::Single))?;
+    let second_single =
+        calculate_taxes_with_standard_deduction(&with_filing_status(second, FilingStatus::Single))?;
+    let mut married_jointly =
+        calculate_taxes_with_standard_deduction(&combine_joint_return(first, second)?)?;
+    married_jointly.employments = combine_employment_results(first, second)?;
+    married_jointly.compensation =
+        combine_compensation_results(first, second, married_jointly.net_income)?;
+    let first_separately = calculate_taxes_with_standard_deduction(&with_filing_status(
+        first,
+        FilingStatus::MarriedFilingSeparately,
+    ))?;
+    let second_separately = calculate_taxes_with_standard_deduction(&with_filing_status(
+        second,
+        FilingStatus::MarriedFilingSeparately,
+    ))?;
+
+    Ok(MarriageAnalysis {
+        first_single,
+        second_single,
+        married_jointly,
+        first_separately,
+        second_separately,
+    })
+}
+
+/// Copies the tax info with a different filing status
+fn with_filing_status(info: &TaxInfo, filing_status: FilingStatus) -> TaxInfo {
+    TaxInfo {
+        filing_status,
+        ..info.clone()
+    }
+}
+
+/// Combines the income, deductions and withholding of two people into a single joint return.
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when the two people do not share a state tax rate
+fn combine_joint_return(first: &TaxInfo, second: &TaxInfo) -> EstimaterResult<TaxInfo> {
+    if first.state_tax_rate_percent != second.state_tax_rate_percent {
+        return Err(EstimaterErrors::UserError(format!(
+            "A joint return needs a single state tax rate, got {} and {}",
+            first.state_tax_rate_percent, second.state_tax_rate_percent
+        )));
+    }
+
+    Ok(TaxInfo {
+        // Hourly wages are folded into the salary (to the dollar), as only one hourly rate fits
+        gross_yearly_income: (first.regular_wages() + second.regular_wages()).round() as u64,
+        hourly: HourlyWage::default(),
+        pre_tax_deducations: first.pre_tax_deducations + second.pre_tax_deducations,
+        deductions: [first.deductions.clone(), second.deductions.clone()].concat(),
+        roth_deferrals: first.roth_deferrals + second.roth_deferrals,
+        after_tax_contributions: first.after_tax_contributions + second.after_tax_contributions,
+        bonus_income: first.bonus_income + second.bonus_income,
+        rsu_vests: [first.rsu_vests.clone(), second.rsu_vests.clone()].concat(),
+        espp_sales: [first.espp_sales.clone(), second.espp_sales.clone()].concat(),
+        iso_exercises: [first.iso_exercises.clone(), second.iso_exercises.clone()].concat(),
+        employments: [first.employments.clone(), second.employments.clone()].concat(),
+        // Each employer's benefits depend on its own employee's pay, so they are combined
+        // separately
+        benefits: EmployerBenefits::default(),
+        federal_withholding_ytd: first.federal_withholding_ytd + second.federal_withholding_ytd,
+        federal_withholding_projected: first.federal_withholding_projected
+            + second.federal_withholding_projected,
+        state_withholding_ytd: first.state_withholding_ytd + second.state_withholding_ytd,
+        state_withholding_projected: first.state_withholding_projected
+            + second.state_withholding_projected,
+        filing_status: FilingStatus::MarriedFilingJointly,
+        ..first.clone()
+    })
+}
+
+/// Combines the Social Security and Medicare withheld from each person on a joint return.
+/// The Social Security wage base applies to each person, so each claims their own excess.
+fn combine_employment_results(
+    first: &TaxInfo,
+    second: &TaxInfo,
+) -> EstimaterResult<Option<EmploymentResults>> {
+    let mut combined: Option<EmploymentResults> = None;
+    for info in [first, second] {
+        if info.employments.is_empty() {
+            continue;
+        }
+        let results = calculate_employment_results(&info.employments)?;
+        combined = Some(match combined {
+            None => results,
+            Some(mut combined) => {
+                combined.employers.extend(results.employers);
+                combined.max_social_security += results.max_social_security;
+                combined.excess_social_security += results.excess_social_security;
+                combined
+            }
+        });
+    }
+    Ok(combined)
+}
+
+/// Combines the employer benefits of each person on a joint return. Each employer matches on
+/// its own employee's pay and deferrals.
+fn combine_compensation_results(
+    first: &TaxInfo,
+    second: &TaxInfo,
+    net_income: f64,
+) -> EstimaterResult<Option<CompensationResults>> {
+    if first.benefits.is_unset() && second.benefits.is_unset() {
+        return Ok(None);
+    }
+    let mut combined = calculate_compensation_results(first, net_income)?;
+    let second = calculate_compensation_results(second, net_income)?;
+    combined.wages += second.wages;
+    combined.employer_match += second.employer_match;
+    combined.employer_hsa += second.employer_hsa;
+    combined.other_benefits += second.other_benefits;
+    Ok(Some(combined))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::use_shipped_data;
+    use chrono::NaiveDate;
+    use estimate_common::employment::Employment;
+
+    fn help_make_info(gross_yearly_income: u64) -> TaxInfo {
+        TaxInfo {
+            gross_yearly_income,
+            state_tax_rate_percent: 5.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_equal_incomes_are_marriage_neutral() {
+        use_shipped_data();
+        let analysis = analyze_marriage(&help_make_info(50000), &help_make_info(50000))
+            .expect("Marriage analysis should've worked");
+        assert!(
+            analysis.total_tax_jointly() == 13481.0,
+            "Expected: 13481.0. Got: {}",
+            analysis.total_tax_jointly()
+        );
+        assert!(
+            analysis.joint_marriage_penalty() == 0.0,
+            "Expected no penalty. Got: {}",
+            analysis.joint_marriage_penalty()
+        );
+        assert!(
+            analysis.separate_marriage_penalty() == 0.0,
+            "Expected no penalty. Got: {}",
+            analysis.separate_marriage_penalty()
+        );
+    }
+
+    #[test]
+    fn test_single_earner_gets_marriage_bonus() {
+        use_shipped_data();
+        let analysis = analyze_marriage(&help_make_info(100000), &help_make_info(0))
+            .expect("Marriage analysis should've worked");
+        // 2055 + 12% of (100000 - 25900 - 20550)
+        assert!(
+            analysis.married_jointly.federal_tax == 8481.0,
+            "Expected: 8481.0. Got: {}",
+            analysis.married_jointly.federal_tax
+        );
+        // Filing as single, 4807.5 + 22% of (100000 - 12950 - 41775) = 14768, and the second
+        // person's unused standard deduction is lost
+        assert!(
+            analysis.joint_marriage_penalty() == -6287.0,
+            "Expected: -6287.0. Got: {}",
+            analysis.joint_marriage_penalty()
+        );
+    }
+
+    #[test]
+    fn test_joint_return_requires_matching_state_rate() {
+        let mut second = help_make_info(50000);
+        second.state_tax_rate_percent = 0.0;
+        assert!(
+            analyze_marriage(&help_make_info(50000), &second).is_err(),
+            "Mismatched state tax rates did not error as expected"
+        );
+    }
+
+    #[test]
+    fn test_joint_return_keeps_excess_social_security_per_person() {
+        use_shipped_data();
+        let employment = |employer: &str, start_month, wages| Employment {
+            employer: employer.to_string(),
+            start_date: NaiveDate::from_ymd_opt(2023, start_month, 1).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2023, start_month + 5, 30).unwrap(),
+            wages,
+            federal_withholding: 0.0,
+            state_withholding: 0.0,
+            social_security_withheld: None,
+            medicare_withheld: None,
+        };
+        let mut first = help_make_info(0);
+        first.employments = vec![
+            employment("First Old", 1, 100000.0),
+            employment("First New", 7, 100000.0),
+        ];
+        let mut second = help_make_info(0);
+        second.employments = vec![employment("Second", 1, 100000.0)];
+
+        let analysis =
+            analyze_marriage(&first, &second).expect("Marriage analysis should've worked");
+        let employments = analysis
+            .married_jointly
+            .employments
+            .expect("Employment results should be set");
+        assert_eq!(employments.employers.len(), 3);
+        // Only the first person had two employers withhold past the wage base
+        assert_eq!(employments.excess_social_security, 2467.6);
+    }
+}
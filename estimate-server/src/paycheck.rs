@@ -0,0 +1,478 @@
+/// Calculates what is withheld from a single paycheck, and from every paycheck over a year.
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::Deserialize;
+
+use estimate_common::{
+    common::TaxInfo,
+    errors::{EstimaterErrors, EstimaterResult},
+    paycheck::{
+        PayFrequency, PaycheckInfo, PaycheckSchedule, PaycheckScheduleInfo, PaycheckWithholding,
+        ScheduledPaycheck,
+    },
+};
+
+use crate::{
+    data::read_data_file, server::get_federal_bracket_file, tax_bracket::TaxBrackets,
+    utils::round_to_hundredths,
+};
+
+/// The employee's share of Social Security tax
+pub(crate) const SOCIAL_SECURITY_RATE: f64 = 0.062;
+/// The employee's share of Medicare tax
+pub(crate) const MEDICARE_RATE: f64 = 0.0145;
+/// The extra Medicare tax withheld on an employee's wages beyond the threshold
+pub(crate) const ADDITIONAL_MEDICARE_RATE: f64 = 0.009;
+/// The wages from one employer after which the additional Medicare tax is withheld
+pub(crate) const ADDITIONAL_MEDICARE_THRESHOLD: f64 = 200000.0;
+/// The data file listing the Social Security wage base of each year
+const WAGE_BASE_FILE: &str = "social_security_wage_bases.json";
+
+/// Struct representing the Social Security wage base of each year.
+#[derive(Debug, Deserialize)]
+pub(crate) struct WageBaseTable {
+    wage_bases: Vec<WageBase>,
+}
+
+/// The most wages Social Security tax is withheld on in a year.
+#[derive(Debug, Deserialize)]
+struct WageBase {
+    tax_year: i32,
+    wage_base: f64,
+}
+
+/// Calculates the withholding from one regular paycheck of the salary and hourly wages.
+///
+/// Federal income tax is withheld by annualizing the paycheck's taxable wages, taxing them with
+/// the federal brackets, and spreading that tax back over the paychecks. State income tax is
+/// withheld at the flat state rate. Supplemental wages (i.e. bonuses) are paid separately, so
+/// they are not part of a regular paycheck.
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when the pre-tax deductions are more than the paycheck
+/// * `Error`: Some other error explaining why the withholding could not be calculated
+/// * `Ok(PaycheckWithholding)`: The paycheck, from its gross pay down to its net pay
+pub fn calculate_paycheck(
+    input_info: &TaxInfo,
+    paycheck_info: &PaycheckInfo,
+) -> EstimaterResult<PaycheckWithholding> {
+    let periods = paycheck_info.pay_frequency.periods_per_year() as f64;
+    let gross_pay = round_to_hundredths(input_info.regular_wages() / periods);
+    let taxable_pay = gross_pay - paycheck_info.pre_tax_deductions;
+    if taxable_pay < 0.0 {
+        return Err(EstimaterErrors::UserError(format!(
+            "The pre-tax deductions of {} are more than the gross pay of {gross_pay}",
+            paycheck_info.pre_tax_deductions
+        )));
+    }
+
+    let tax_brackets =
+        TaxBrackets::from_data_file(get_federal_bracket_file(&input_info.filing_status))?;
+    let (federal_withholding, state_withholding) =
+        withhold_income_tax(&tax_brackets, input_info, periods, taxable_pay, taxable_pay)?;
+
+    Ok(PaycheckWithholding {
+        gross_pay,
+        pre_tax_deductions: paycheck_info.pre_tax_deductions,
+        federal_withholding,
+        state_withholding,
+        social_security: round_to_hundredths(gross_pay * SOCIAL_SECURITY_RATE),
+        medicare: round_to_hundredths(gross_pay * MEDICARE_RATE),
+        post_tax_deductions: paycheck_info.post_tax_deductions,
+    })
+}
+
+/// Lists every regular paycheck of the salary and hourly wages over a tax year.
+///
+/// The salary and hourly wages and the yearly pre-tax deductions are split evenly across the pay
+/// dates, with the last paycheck taking any rounding difference. Each deduction is excluded
+/// from federal, state and FICA wages by its type. Social Security tax stops once the year's
+/// FICA wages reach the wage base, and the additional Medicare tax starts once they pass its
+/// threshold.
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when the pay dates or wage base can't be determined for the
+///   year, or the pre-tax deductions are more than a paycheck
+/// * `Error`: Some other error explaining why the withholding could not be calculated
+/// * `Ok(PaycheckSchedule)`: Every paycheck of the year, in order
+pub fn build_paycheck_schedule(
+    input_info: &TaxInfo,
+    schedule_info: &PaycheckScheduleInfo,
+) -> EstimaterResult<PaycheckSchedule> {
+    let pay_dates = get_pay_dates(
+        schedule_info.tax_year,
+        schedule_info.pay_frequency,
+        schedule_info.first_pay_date,
+    )?;
+    let social_security_wage_base =
+        WageBaseTable::from_data_file()?.wage_base_for(schedule_info.tax_year)?;
+    let tax_brackets =
+        TaxBrackets::from_data_file(get_federal_bracket_file(&input_info.filing_status))?;
+    // Withholding is annualized over the usual number of paychecks, even in years with an
+    // extra pay date
+    let periods = schedule_info.pay_frequency.periods_per_year() as f64;
+
+    let gross_pays = split_evenly(input_info.regular_wages(), pay_dates.len());
+    let pre_tax_deductions = split_evenly(input_info.total_pre_tax_deductions(), pay_dates.len());
+    let state_deductions = split_evenly(input_info.state_pre_tax_deductions(), pay_dates.len());
+    let fica_exempt_deductions = split_evenly(input_info.fica_exempt_deductions(), pay_dates.len());
+    let mut wages_to_date = 0.0;
+    let mut paychecks = Vec::with_capacity(pay_dates.len());
+    for (index, (pay_date, gross_pay)) in pay_dates.into_iter().zip(gross_pays).enumerate() {
+        let pre_tax_deductions = pre_tax_deductions[index];
+        let taxable_pay = gross_pay - pre_tax_deductions;
+        if taxable_pay < 0.0 {
+            return Err(EstimaterErrors::UserError(format!(
+                "The pre-tax deductions of {pre_tax_deductions} on {pay_date} are more than the \
+                 gross pay of {gross_pay}"
+            )));
+        }
+        let (federal_withholding, state_withholding) = withhold_income_tax(
+            &tax_brackets,
+            input_info,
+            periods,
+            taxable_pay,
+            gross_pay - state_deductions[index],
+        )?;
+
+        let fica_wages = gross_pay - fica_exempt_deductions[index];
+        let social_security_wages =
+            (social_security_wage_base - wages_to_date).clamp(0.0, fica_wages);
+        let additional_medicare_wages = (wages_to_date + fica_wages
+            - ADDITIONAL_MEDICARE_THRESHOLD.max(wages_to_date))
+        .max(0.0);
+        let reaches_wage_base = wages_to_date < social_security_wage_base
+            && wages_to_date + fica_wages >= social_security_wage_base;
+        wages_to_date += fica_wages;
+
+        paychecks.push(ScheduledPaycheck {
+            pay_date,
+            paycheck: PaycheckWithholding {
+                gross_pay,
+                pre_tax_deductions,
+                federal_withholding,
+                state_withholding,
+                social_security: round_to_hundredths(social_security_wages * SOCIAL_SECURITY_RATE),
+                medicare: round_to_hundredths(
+                    fica_wages * MEDICARE_RATE
+                        + additional_medicare_wages * ADDITIONAL_MEDICARE_RATE,
+                ),
+                post_tax_deductions: 0.0,
+            },
+            reaches_wage_base,
+        });
+    }
+
+    Ok(PaycheckSchedule {
+        pay_frequency: schedule_info.pay_frequency,
+        social_security_wage_base,
+        paychecks,
+    })
+}
+
+/// Withholds federal and state income tax from a paycheck's federal and state taxable wages.
+///
+/// Federal tax is withheld by annualizing the taxable wages, taxing them with the federal
+/// brackets, and spreading that tax back over the paychecks. State tax is withheld at the flat
+/// state rate.
+///
+/// # Return
+///
+/// * `Ok((f64, f64))`: The federal and state withholding
+fn withhold_income_tax(
+    tax_brackets: &TaxBrackets,
+    input_info: &TaxInfo,
+    periods: f64,
+    taxable_pay: f64,
+    state_taxable_pay: f64,
+) -> EstimaterResult<(f64, f64)> {
+    let annual_federal_tax = tax_brackets.calculate_tax_amount(taxable_pay * periods)?;
+    Ok((
+        round_to_hundredths(annual_federal_tax / periods),
+        round_to_hundredths(state_taxable_pay * input_info.state_tax_rate_percent / 100.0),
+    ))
+}
+
+/// Splits an amount into a number of parts rounded to the cent, with the last part taking the
+/// rounding difference so the parts add up to the amount.
+fn split_evenly(amount: f64, parts: usize) -> Vec<f64> {
+    let part = round_to_hundredths(amount / parts as f64);
+    let mut split = vec![part; parts];
+    if let Some(last) = split.last_mut() {
+        *last = round_to_hundredths(amount - part * (parts - 1) as f64);
+    }
+    split
+}
+
+/// Gets every pay date in a tax year.
+///
+/// Weekly and biweekly paychecks repeat from the first pay date (the first Friday of the year
+/// by default). Semi-monthly paychecks are paid on the 15th and last day of each month, and
+/// monthly paychecks on the last day.
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when the tax year is invalid or the first pay date is not in it
+/// * `Ok(Vec<NaiveDate>)`: The pay dates in order
+pub(crate) fn get_pay_dates(
+    tax_year: i32,
+    pay_frequency: PayFrequency,
+    first_pay_date: Option<NaiveDate>,
+) -> EstimaterResult<Vec<NaiveDate>> {
+    let invalid_year = || EstimaterErrors::UserError(format!("Invalid tax year {tax_year}"));
+    let step_days = match pay_frequency {
+        PayFrequency::Weekly => 7,
+        PayFrequency::Biweekly => 14,
+        PayFrequency::SemiMonthly | PayFrequency::Monthly => {
+            let mut pay_dates = Vec::new();
+            for month in 1..=12 {
+                let last_day = last_day_of_month(tax_year, month).ok_or_else(invalid_year)?;
+                if pay_frequency == PayFrequency::SemiMonthly {
+                    pay_dates.push(last_day.with_day(15).ok_or_else(invalid_year)?);
+                }
+                pay_dates.push(last_day);
+            }
+            return Ok(pay_dates);
+        }
+    };
+
+    let first_pay_date = match first_pay_date {
+        Some(first_pay_date) if first_pay_date.year() != tax_year => {
+            return Err(EstimaterErrors::UserError(format!(
+                "The first pay date {first_pay_date} is not in the tax year {tax_year}"
+            )))
+        }
+        Some(first_pay_date) => first_pay_date,
+        None => {
+            let new_year = NaiveDate::from_ymd_opt(tax_year, 1, 1).ok_or_else(invalid_year)?;
+            let days_to_friday = (Weekday::Fri.num_days_from_monday() + 7
+                - new_year.weekday().num_days_from_monday())
+                % 7;
+            new_year + Duration::days(days_to_friday as i64)
+        }
+    };
+    Ok(std::iter::successors(Some(first_pay_date), |pay_date| {
+        Some(*pay_date + Duration::days(step_days))
+    })
+    .take_while(|pay_date| pay_date.year() == tax_year)
+    .collect())
+}
+
+/// Gets the last day of a month
+fn last_day_of_month(year: i32, month: u32) -> Option<NaiveDate> {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)?.pred_opt()
+}
+
+impl WageBaseTable {
+    /// Attempts to read the data file containing the wage bases.
+    ///
+    /// # Return
+    ///
+    /// * Error if file doesn't exist (or something else)
+    /// * Success: WageBaseTable instance
+    pub(crate) fn from_data_file() -> EstimaterResult<Self> {
+        read_data_file(WAGE_BASE_FILE)
+    }
+
+    /// Gets the wage base of a tax year
+    pub(crate) fn wage_base_for(&self, tax_year: i32) -> EstimaterResult<f64> {
+        self.wage_bases
+            .iter()
+            .find(|wage_base| wage_base.tax_year == tax_year)
+            .map(|wage_base| wage_base.wage_base)
+            .ok_or_else(|| {
+                EstimaterErrors::UserError(format!(
+                    "No Social Security wage base is known for {tax_year}. Add it to \
+                     {WAGE_BASE_FILE} in a data directory."
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::use_shipped_data;
+    use estimate_common::deductions::{DeductionType, PreTaxDeduction};
+
+    #[test]
+    fn test_calculate_paycheck() {
+        use_shipped_data();
+        let info = TaxInfo {
+            gross_yearly_income: 52000,
+            state_tax_rate_percent: 5.0,
+            ..Default::default()
+        };
+        let paycheck_info = PaycheckInfo {
+            pay_frequency: PayFrequency::Weekly,
+            pre_tax_deductions: 100.0,
+            post_tax_deductions: 50.0,
+        };
+        let paycheck =
+            calculate_paycheck(&info, &paycheck_info).expect("Paycheck should've calculated");
+
+        assert_eq!(paycheck.gross_pay, 1000.0);
+        // 900 a week is 46800 a year, which is taxed 5913 by the brackets
+        assert_eq!(paycheck.federal_withholding, 113.71);
+        assert_eq!(paycheck.state_withholding, 45.0);
+        assert_eq!(paycheck.social_security, 62.0);
+        assert_eq!(paycheck.medicare, 14.5);
+        assert_eq!(
+            paycheck.net_pay(),
+            1000.0 - 100.0 - 113.71 - 45.0 - 62.0 - 14.5 - 50.0
+        );
+    }
+
+    #[test]
+    fn test_get_pay_dates() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2023, month, day).unwrap();
+
+        let biweekly = get_pay_dates(2023, PayFrequency::Biweekly, None).unwrap();
+        assert_eq!(biweekly.len(), 26);
+        assert_eq!(biweekly[0], date(1, 6));
+        assert_eq!(biweekly[25], date(12, 22));
+
+        // Starting on the first day of the year fits an extra paycheck in
+        let weekly = get_pay_dates(2023, PayFrequency::Weekly, Some(date(1, 1))).unwrap();
+        assert_eq!(weekly.len(), 53);
+        assert_eq!(weekly[52], date(12, 31));
+
+        let semi_monthly = get_pay_dates(2024, PayFrequency::SemiMonthly, None).unwrap();
+        assert_eq!(semi_monthly.len(), 24);
+        assert_eq!(
+            semi_monthly[2],
+            NaiveDate::from_ymd_opt(2024, 2, 15).unwrap()
+        );
+        assert_eq!(
+            semi_monthly[3],
+            NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()
+        );
+
+        let monthly = get_pay_dates(2023, PayFrequency::Monthly, None).unwrap();
+        assert_eq!(monthly.len(), 12);
+        assert_eq!(monthly[11], date(12, 31));
+
+        assert!(get_pay_dates(
+            2023,
+            PayFrequency::Weekly,
+            Some(date(1, 1).pred_opt().unwrap())
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_build_paycheck_schedule() {
+        use_shipped_data();
+        let info = TaxInfo {
+            gross_yearly_income: 240000,
+            pre_tax_deducations: 24000.0,
+            state_tax_rate_percent: 5.0,
+            ..Default::default()
+        };
+        let schedule_info = PaycheckScheduleInfo {
+            tax_year: 2023,
+            pay_frequency: PayFrequency::Monthly,
+            first_pay_date: None,
+        };
+        let schedule =
+            build_paycheck_schedule(&info, &schedule_info).expect("Schedule should've built");
+
+        assert_eq!(schedule.paychecks.len(), 12);
+        assert_eq!(schedule.social_security_wage_base, 160200.0);
+        let paychecks: Vec<&PaycheckWithholding> = schedule
+            .paychecks
+            .iter()
+            .map(|scheduled| &scheduled.paycheck)
+            .collect();
+        assert_eq!(paychecks[0].gross_pay, 20000.0);
+        assert_eq!(paychecks[0].pre_tax_deductions, 2000.0);
+        assert_eq!(paychecks[0].state_withholding, 900.0);
+        assert_eq!(paychecks[0].social_security, 1240.0);
+        assert_eq!(paychecks[0].medicare, 290.0);
+
+        // The 9th paycheck reaches the wage base, 160000 of wages having come before it
+        let reached: Vec<bool> = schedule
+            .paychecks
+            .iter()
+            .map(|scheduled| scheduled.reaches_wage_base)
+            .collect();
+        assert_eq!(reached.iter().filter(|reached| **reached).count(), 1);
+        assert!(reached[8]);
+        assert_eq!(paychecks[8].social_security, 12.4);
+        assert_eq!(paychecks[9].social_security, 0.0);
+
+        // The 11th paycheck passes 200000 of wages, so the additional Medicare tax starts
+        assert_eq!(paychecks[9].medicare, 290.0);
+        assert_eq!(paychecks[10].medicare, 290.0 + 180.0);
+        assert_eq!(paychecks[11].medicare, 290.0 + 180.0);
+
+        let totals = schedule.totals();
+        assert_eq!(totals.gross_pay, 240000.0);
+        assert_eq!(totals.pre_tax_deductions, 24000.0);
+        assert_eq!(round_to_hundredths(totals.social_security), 9932.4);
+    }
+
+    #[test]
+    fn test_build_paycheck_schedule_fica_exempt_deductions() {
+        use_shipped_data();
+        let info = TaxInfo {
+            gross_yearly_income: 120000,
+            deductions: vec![
+                PreTaxDeduction::new(DeductionType::Traditional401k, 12000.0),
+                PreTaxDeduction::new(DeductionType::HealthPremiums, 6000.0),
+            ],
+            ..Default::default()
+        };
+        let schedule_info = PaycheckScheduleInfo {
+            tax_year: 2023,
+            pay_frequency: PayFrequency::Monthly,
+            first_pay_date: None,
+        };
+        let schedule =
+            build_paycheck_schedule(&info, &schedule_info).expect("Schedule should've built");
+
+        // Both deductions lower income tax, but only the premiums lower FICA wages
+        let paycheck = &schedule.paychecks[0].paycheck;
+        assert_eq!(paycheck.pre_tax_deductions, 1500.0);
+        assert_eq!(paycheck.social_security, 589.0);
+        assert_eq!(paycheck.medicare, 137.75);
+    }
+
+    #[test]
+    fn test_build_paycheck_schedule_unknown_year() {
+        use_shipped_data();
+        let schedule_info = PaycheckScheduleInfo {
+            tax_year: 1990,
+            pay_frequency: PayFrequency::Monthly,
+            first_pay_date: None,
+        };
+        assert!(matches!(
+            build_paycheck_schedule(&TaxInfo::default(), &schedule_info),
+            Err(EstimaterErrors::UserError(_))
+        ));
+    }
+
+    #[test]
+    fn test_calculate_paycheck_deductions_above_pay() {
+        let info = TaxInfo {
+            gross_yearly_income: 12000,
+            ..Default::default()
+        };
+        let paycheck_info = PaycheckInfo {
+            pay_frequency: PayFrequency::Monthly,
+            pre_tax_deductions: 1500.0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            calculate_paycheck(&info, &paycheck_info),
+            Err(EstimaterErrors::UserError(_))
+        ));
+    }
+}
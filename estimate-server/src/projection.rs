@@ -0,0 +1,264 @@
+/// Projects taxes and net income over the coming years, as pay grows and the federal brackets
+/// are adjusted for inflation.
+use estimate_common::{
+    common::TaxInfo,
+    deductions::{DeductionType, PreTaxDeduction},
+    errors::{EstimaterErrors, EstimaterResult},
+    projection::{ProjectedYear, Projection, ProjectionInfo},
+};
+
+use crate::{
+    retirement::PlanLimitsTable,
+    server::{calculate_taxes_with_brackets, get_federal_bracket_file},
+    tax_bracket::TaxBrackets,
+    utils::round_to_hundredths,
+};
+
+/// Calculates the taxes and net income of each year of a projection.
+///
+/// The first year is the tax info as given. Every year after it, the salary, hourly rate and
+/// bonus grow by the salary growth, and the federal bracket thresholds by the bracket
+/// inflation. Employments, RSU vests, ESPP sales and ISO exercises only happen once, so they
+/// are only part of the first year. The state tax rate stays the same. A contribution plan is
+/// capped by each year's 401(k) elective deferral limit, which stays at the last known limit
+/// after the years in the data file.
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when there are no years, a rate is -100% or lower, the
+///   contributions are negative, or there is a contribution plan and the first year has no
+///   known 401(k) limits
+/// * `Error`: Some other error explaining why the taxes of a year could not be calculated
+/// * `Ok(Projection)`: The taxes and net income of each year
+pub fn project_taxes(
+    input_info: &TaxInfo,
+    projection_info: &ProjectionInfo,
+) -> EstimaterResult<Projection> {
+    validate_projection_info(projection_info)?;
+    let salary_growth = 1.0 + projection_info.salary_growth_percent / 100.0;
+    let bracket_inflation = 1.0 + projection_info.bracket_inflation_percent / 100.0;
+    let bracket_file = get_federal_bracket_file(&input_info.filing_status);
+    let limits_table = match projection_info.contribution_percent {
+        Some(_) => Some(PlanLimitsTable::from_data_file()?),
+        None => None,
+    };
+
+    let mut years = Vec::with_capacity(projection_info.years as usize);
+    for year_index in 0..projection_info.years {
+        let deferral_limit = limits_table
+            .as_ref()
+            .map(|table| {
+                table.elective_deferral_limit(projection_info.tax_year + year_index as i32)
+            })
+            .transpose()?;
+        let year_info = project_tax_info(
+            input_info,
+            projection_info,
+            year_index,
+            salary_growth,
+            deferral_limit,
+        );
+        let mut tax_brackets = TaxBrackets::from_data_file(bracket_file)?;
+        tax_brackets.inflate(bracket_inflation.powi(year_index as i32))?;
+        let results = calculate_taxes_with_brackets(&year_info, &tax_brackets, 0.0)?;
+
+        years.push(ProjectedYear {
+            year: year_index + 1,
+            wages: round_to_hundredths(year_info.total_wages()),
+            pre_tax_deductions: round_to_hundredths(year_info.total_pre_tax_deductions()),
+            federal_tax: round_to_hundredths(results.federal_tax),
+            state_tax: round_to_hundredths(results.state_tax),
+            net_income: round_to_hundredths(results.net_income),
+        });
+    }
+
+    Ok(Projection {
+        salary_growth_percent: projection_info.salary_growth_percent,
+        bracket_inflation_percent: projection_info.bracket_inflation_percent,
+        years,
+    })
+}
+
+/// Checks the projection can be calculated
+fn validate_projection_info(projection_info: &ProjectionInfo) -> EstimaterResult<()> {
+    if projection_info.years == 0 {
+        return Err(EstimaterErrors::UserError(
+            "A projection needs at least 1 year".to_string(),
+        ));
+    }
+    if projection_info.salary_growth_percent <= -100.0
+        || projection_info.bracket_inflation_percent <= -100.0
+    {
+        return Err(EstimaterErrors::UserError(format!(
+            "The salary growth of {}% and bracket inflation of {}% must be more than -100%",
+            projection_info.salary_growth_percent, projection_info.bracket_inflation_percent
+        )));
+    }
+    if projection_info.contribution_percent.unwrap_or(0.0) < 0.0
+        || projection_info.contribution_increase_percent < 0.0
+    {
+        return Err(EstimaterErrors::UserError(
+            "The contribution percent and its increase can not be negative".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Gets the tax info of a year of the projection, `year_index` years after the first
+///
+/// # Params
+/// * `deferral_limit` - The 402(g) elective deferral limit of the year. Only needed with a
+///   contribution plan.
+fn project_tax_info(
+    input_info: &TaxInfo,
+    projection_info: &ProjectionInfo,
+    year_index: u32,
+    salary_growth: f64,
+    deferral_limit: Option<f64>,
+) -> TaxInfo {
+    if year_index == 0 && projection_info.contribution_percent.is_none() {
+        return input_info.clone();
+    }
+    let growth = salary_growth.powi(year_index as i32);
+    let mut year_info = input_info.clone();
+    if year_index > 0 {
+        year_info.gross_yearly_income =
+            (input_info.gross_yearly_income as f64 * growth).round() as u64;
+        year_info.hourly.hourly_rate = round_to_hundredths(input_info.hourly.hourly_rate * growth);
+        year_info.bonus_income = round_to_hundredths(input_info.bonus_income * growth);
+        year_info.employments.clear();
+        year_info.rsu_vests.clear();
+        year_info.espp_sales.clear();
+        year_info.iso_exercises.clear();
+    }
+
+    if let Some(contribution_percent) = projection_info.contribution_percent {
+        let percent = (contribution_percent
+            + projection_info.contribution_increase_percent * year_index as f64)
+            .min(100.0);
+        year_info
+            .deductions
+            .retain(|deduction| deduction.deduction_type != DeductionType::Traditional401k);
+        // The 403(b) and Roth deferrals kept share the limit
+        let deferral_room = deferral_limit
+            .map_or(f64::MAX, |limit| limit - year_info.elective_deferrals())
+            .max(0.0);
+        year_info.deductions.push(PreTaxDeduction::new(
+            DeductionType::Traditional401k,
+            round_to_hundredths(year_info.regular_wages() * percent / 100.0).min(deferral_room),
+        ));
+    }
+    year_info
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::use_shipped_data;
+
+    fn help_make_projection_info(years: u32) -> ProjectionInfo {
+        ProjectionInfo {
+            tax_year: 2023,
+            years,
+            salary_growth_percent: 0.0,
+            bracket_inflation_percent: 0.0,
+            contribution_percent: None,
+            contribution_increase_percent: 0.0,
+        }
+    }
+
+    fn help_make_info(gross_yearly_income: u64) -> TaxInfo {
+        TaxInfo {
+            gross_yearly_income,
+            state_tax_rate_percent: 5.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_flat_projection_repeats_the_first_year() {
+        use_shipped_data();
+        let projection = project_taxes(&help_make_info(50000), &help_make_projection_info(3))
+            .expect("Projection should've calculated");
+        assert_eq!(projection.years.len(), 3);
+        for year in projection.years.iter() {
+            assert_eq!(year.federal_tax, 6617.0);
+            assert_eq!(year.state_tax, 2500.0);
+        }
+        let totals = projection.totals();
+        assert_eq!(totals.wages, 150000.0);
+        assert_eq!(totals.federal_tax, 19851.0);
+    }
+
+    #[test]
+    fn test_growth_matched_by_inflation() {
+        use_shipped_data();
+        let mut projection_info = help_make_projection_info(2);
+        projection_info.salary_growth_percent = 10.0;
+        let projection = project_taxes(&help_make_info(50000), &projection_info)
+            .expect("Projection should've calculated");
+        // Without inflation, the raise is taxed at the 22% bracket
+        assert_eq!(projection.years[1].wages, 55000.0);
+        assert_eq!(projection.years[1].federal_tax, 6617.0 + 1100.0);
+
+        // With the brackets moved up by as much, the tax grows by 10% too, less the rounding of
+        // the thresholds
+        projection_info.bracket_inflation_percent = 10.0;
+        let projection = project_taxes(&help_make_info(50000), &projection_info)
+            .expect("Projection should've calculated");
+        assert_eq!(projection.years[1].federal_tax, 7278.64);
+    }
+
+    #[test]
+    fn test_contribution_plan() {
+        use_shipped_data();
+        let mut info = help_make_info(100000);
+        info.pre_tax_deducations = 20000.0;
+        info.deductions = vec![PreTaxDeduction::new(DeductionType::Hsa, 3000.0)];
+        let mut projection_info = help_make_projection_info(3);
+        projection_info.contribution_percent = Some(5.0);
+        projection_info.contribution_increase_percent = 1.0;
+        let projection =
+            project_taxes(&info, &projection_info).expect("Projection should've calculated");
+
+        // The plan adds to the untyped deductions and the HSA
+        assert_eq!(projection.years[0].pre_tax_deductions, 28000.0);
+        assert_eq!(projection.years[1].pre_tax_deductions, 29000.0);
+        assert_eq!(projection.years[2].pre_tax_deductions, 30000.0);
+    }
+
+    #[test]
+    fn test_contribution_plan_capped_by_deferral_limit() {
+        use_shipped_data();
+        let mut info = help_make_info(300000);
+        info.roth_deferrals = 2500.0;
+        let mut projection_info = help_make_projection_info(4);
+        projection_info.contribution_percent = Some(10.0);
+        projection_info.contribution_increase_percent = 1.0;
+        let projection =
+            project_taxes(&info, &projection_info).expect("Projection should've calculated");
+
+        // 30000 and more is asked for, but only the limits of 2023 to 2025 less the Roth
+        // deferrals are left. 2026 keeps the limit of 2025.
+        let deductions: Vec<f64> = projection
+            .years
+            .iter()
+            .map(|year| year.pre_tax_deductions)
+            .collect();
+        assert_eq!(deductions, vec![20000.0, 20500.0, 21000.0, 21000.0]);
+
+        projection_info.tax_year = 1999;
+        assert!(project_taxes(&info, &projection_info).is_err());
+    }
+
+    #[test]
+    fn test_invalid_projection() {
+        let info = help_make_info(50000);
+        assert!(project_taxes(&info, &help_make_projection_info(0)).is_err());
+
+        let mut projection_info = help_make_projection_info(1);
+        projection_info.salary_growth_percent = -100.0;
+        assert!(project_taxes(&info, &projection_info).is_err());
+    }
+}
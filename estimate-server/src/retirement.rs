@@ -0,0 +1,227 @@
+/// Compares the contributions to a 401(k) plan to its yearly limits, and finds the room left
+/// for after-tax contributions (i.e. a mega-backdoor Roth).
+use serde::Deserialize;
+
+use estimate_common::{
+    common::TaxInfo,
+    errors::{EstimaterErrors, EstimaterResult},
+    retirement::{AnnualAdditions, RetirementPlanInfo},
+};
+
+use crate::{
+    benefits::{calculate_employer_match, matched_pay},
+    data::read_data_file,
+};
+
+/// The data file listing the 401(k) limits of each tax year
+const PLAN_LIMITS_FILE: &str = "retirement_plan_limits.json";
+
+/// Struct representing the 401(k) limits of every known tax year.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PlanLimitsTable {
+    limits: Vec<PlanLimits>,
+}
+
+/// The 401(k) limits of a single tax year.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PlanLimits {
+    tax_year: i32,
+    /// The section 402(g) limit on elective deferrals
+    elective_deferral_limit: f64,
+    /// The deferrals allowed beyond the elective deferral limit at age 50 or over
+    catch_up_limit: f64,
+    /// The section 415(c) dollar limit on annual additions
+    annual_additions_limit: f64,
+}
+
+/// Compares the elective deferrals, employer match and after-tax contributions to the limits
+/// of the plan year.
+///
+/// The pre-tax deferrals are the 401(k) and 403(b) deductions. A governmental 457(b) plan has
+/// its own limit, so its deductions are not part of the 401(k) plan's. The employer match is
+/// calculated from the employer benefits. Converting the after-tax contributions to Roth in the
+/// plan is not taxed, beyond any earnings before the conversion.
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when the year has no known limits, or a contribution is
+///   negative
+/// * `Ok(AnnualAdditions)`: The contributions, the limits and the after-tax room left
+pub fn analyze_annual_additions(
+    input_info: &TaxInfo,
+    plan_info: &RetirementPlanInfo,
+) -> EstimaterResult<AnnualAdditions> {
+    if input_info.roth_deferrals < 0.0 || input_info.after_tax_contributions < 0.0 {
+        return Err(EstimaterErrors::UserError(
+            "Roth deferrals and after-tax contributions can not be negative".to_string(),
+        ));
+    }
+    let limits_table = PlanLimitsTable::from_data_file()?;
+    let limits = limits_table.limits_for(plan_info.tax_year)?;
+    let pay = matched_pay(input_info);
+    let elective_deferrals = input_info.elective_deferrals();
+
+    Ok(AnnualAdditions {
+        tax_year: plan_info.tax_year,
+        elective_deferrals,
+        elective_deferral_limit: limits.elective_deferral_limit,
+        catch_up_limit: if plan_info.catch_up_eligible {
+            limits.catch_up_limit
+        } else {
+            0.0
+        },
+        employer_match: calculate_employer_match(
+            &input_info.benefits.match_tiers,
+            pay,
+            elective_deferrals,
+        )?,
+        after_tax_contributions: input_info.after_tax_contributions,
+        annual_additions_limit: limits.annual_additions_limit.min(pay),
+    })
+}
+
+impl PlanLimitsTable {
+    /// Attempts to read the data file containing the 401(k) limits.
+    ///
+    /// # Return
+    ///
+    /// * Error if file doesn't exist (or something else)
+    /// * Success: PlanLimitsTable instance
+    pub(crate) fn from_data_file() -> EstimaterResult<Self> {
+        read_data_file(PLAN_LIMITS_FILE)
+    }
+
+    /// Gets the section 402(g) elective deferral limit of a tax year. Years after the last known
+    /// one keep its limit.
+    pub(crate) fn elective_deferral_limit(&self, tax_year: i32) -> EstimaterResult<f64> {
+        match self.limits.iter().max_by_key(|limits| limits.tax_year) {
+            Some(latest) if tax_year > latest.tax_year => Ok(latest.elective_deferral_limit),
+            _ => Ok(self.limits_for(tax_year)?.elective_deferral_limit),
+        }
+    }
+
+    /// Gets the limits of a tax year
+    pub(crate) fn limits_for(&self, tax_year: i32) -> EstimaterResult<&PlanLimits> {
+        self.limits
+            .iter()
+            .find(|limits| limits.tax_year == tax_year)
+            .ok_or_else(|| {
+                EstimaterErrors::UserError(format!(
+                    "No 401(k) limits are known for {tax_year}. Add them to {PLAN_LIMITS_FILE} \
+                     in a data directory."
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::use_shipped_data;
+    use estimate_common::{
+        benefits::{EmployerBenefits, MatchTier},
+        deductions::{DeductionType, PreTaxDeduction},
+    };
+
+    fn help_make_plan_info(catch_up_eligible: bool) -> RetirementPlanInfo {
+        RetirementPlanInfo {
+            tax_year: 2023,
+            catch_up_eligible,
+        }
+    }
+
+    fn help_make_info(gross_yearly_income: u64, deferrals: f64) -> TaxInfo {
+        TaxInfo {
+            gross_yearly_income,
+            deductions: vec![PreTaxDeduction::new(
+                DeductionType::Traditional401k,
+                deferrals,
+            )],
+            benefits: EmployerBenefits {
+                match_tiers: vec![MatchTier {
+                    match_percent: 50.0,
+                    of_pay_percent: 6.0,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_remaining_after_tax_room() {
+        use_shipped_data();
+        let mut info = help_make_info(200000, 12500.0);
+        info.roth_deferrals = 10000.0;
+        info.after_tax_contributions = 10000.0;
+        let additions = analyze_annual_additions(&info, &help_make_plan_info(false))
+            .expect("Annual additions should've calculated");
+
+        // 22500 deferred, 6000 matched and 10000 after tax, of 2023's 66000 limit
+        assert_eq!(additions.employer_match, 6000.0);
+        assert_eq!(additions.annual_additions(), 38500.0);
+        assert_eq!(additions.remaining_after_tax_room(), 27500.0);
+        assert_eq!(additions.excess_additions(), 0.0);
+    }
+
+    #[test]
+    fn test_catch_up_deferrals_do_not_count() {
+        use_shipped_data();
+        let info = help_make_info(200000, 30000.0);
+        let additions = analyze_annual_additions(&info, &help_make_plan_info(true))
+            .expect("Annual additions should've calculated");
+        assert_eq!(additions.catch_up_deferrals(), 7500.0);
+        assert_eq!(additions.excess_deferrals(), 0.0);
+        assert_eq!(
+            additions.remaining_after_tax_room(),
+            66000.0 - 22500.0 - 6000.0
+        );
+
+        // Without catch-up eligibility the same deferrals are over the limit
+        let additions = analyze_annual_additions(&info, &help_make_plan_info(false))
+            .expect("Annual additions should've calculated");
+        assert_eq!(additions.catch_up_deferrals(), 0.0);
+        assert_eq!(additions.excess_deferrals(), 7500.0);
+    }
+
+    #[test]
+    fn test_annual_additions_limited_by_pay() {
+        use_shipped_data();
+        let mut info = help_make_info(40000, 20000.0);
+        info.after_tax_contributions = 20000.0;
+        let additions = analyze_annual_additions(&info, &help_make_plan_info(false))
+            .expect("Annual additions should've calculated");
+        assert_eq!(additions.annual_additions_limit, 40000.0);
+        assert_eq!(additions.remaining_after_tax_room(), 0.0);
+        assert_eq!(additions.excess_additions(), 1200.0);
+    }
+
+    #[test]
+    fn test_457b_deferrals_do_not_count() {
+        use_shipped_data();
+        let mut info = help_make_info(200000, 22500.0);
+        info.deductions.push(PreTaxDeduction::new(
+            DeductionType::Governmental457b,
+            22500.0,
+        ));
+        let additions = analyze_annual_additions(&info, &help_make_plan_info(false))
+            .expect("Annual additions should've calculated");
+        assert_eq!(additions.elective_deferrals, 22500.0);
+        assert_eq!(additions.excess_deferrals(), 0.0);
+        assert_eq!(additions.employer_match, 6000.0);
+        assert_eq!(
+            additions.remaining_after_tax_room(),
+            66000.0 - 22500.0 - 6000.0
+        );
+    }
+
+    #[test]
+    fn test_unknown_year() {
+        let plan_info = RetirementPlanInfo {
+            tax_year: 1999,
+            catch_up_eligible: false,
+        };
+        assert!(analyze_annual_additions(&help_make_info(100000, 0.0), &plan_info).is_err());
+    }
+}
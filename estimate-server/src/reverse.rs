@@ -0,0 +1,262 @@
+/// Works backwards from a take home (net) income, or a net paycheck, to the gross income that
+/// produces it.
+use estimate_common::{
+    common::{GrossIncomeSolution, TaxInfo, TaxResults},
+    errors::{EstimaterErrors, EstimaterResult},
+    paycheck::{PaycheckInfo, PaycheckSolution, PaycheckWithholding},
+};
+
+use crate::{paycheck::calculate_paycheck, server::calculate_taxes};
+
+/// The largest gross yearly income searched before giving up on reaching the target
+const MAX_GROSS_YEARLY_INCOME: u64 = 1_000_000_000_000;
+
+/// Finds the gross yearly income needed to reach a net income, keeping every other field of the
+/// tax info as given.
+///
+/// Net income only ever grows with the gross income (no rate reaches 100%), but the brackets,
+/// phase-outs and supplemental wages make it piecewise. So rather than inverting any one
+/// formula, the gross income is searched for with `calculate_taxes` itself.
+///
+/// # Params
+/// * `input_info` - The tax info to solve for. Its `gross_yearly_income` is ignored.
+/// * `target_net_income` - The net income to reach
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when no gross income reaches the target
+/// * `Error`: Some other error explaining why the taxes could not be calculated
+/// * `Ok(GrossIncomeSolution)`: The smallest whole dollar gross income reaching the target
+pub fn solve_gross_income(
+    input_info: &TaxInfo,
+    target_net_income: f64,
+) -> EstimaterResult<GrossIncomeSolution> {
+    check_target(target_net_income)?;
+
+    // Below this the pre-tax deductions are more than the wages, which can't be taxed
+    let other_wages = input_info.total_wages() - input_info.gross_yearly_income as f64;
+    let low = (input_info.total_pre_tax_deductions() - other_wages)
+        .ceil()
+        .max(0.0) as u64;
+    let (gross_yearly_income, results) = search_gross_income(
+        low,
+        target_net_income,
+        |gross_yearly_income| calculate_taxes_at(input_info, gross_yearly_income),
+        |results| results.net_income,
+    )?;
+    Ok(GrossIncomeSolution {
+        target_net_income,
+        gross_yearly_income,
+        results,
+    })
+}
+
+/// Finds the gross yearly income implied by the net pay of a single regular paycheck, keeping
+/// every other field of the tax info as given.
+///
+/// # Params
+/// * `input_info` - The tax info to solve for. Its `gross_yearly_income` is ignored.
+/// * `paycheck_info` - The pay frequency and the deductions taken from each paycheck
+/// * `target_net_pay` - The net pay of a paycheck to reach
+///
+/// # Return
+///
+/// * `EstimaterErrors::UserError` when no gross income reaches the target
+/// * `Error`: Some other error explaining why the withholding could not be calculated
+/// * `Ok(PaycheckSolution)`: The smallest whole dollar gross income reaching the target
+pub fn solve_gross_from_paycheck(
+    input_info: &TaxInfo,
+    paycheck_info: &PaycheckInfo,
+    target_net_pay: f64,
+) -> EstimaterResult<PaycheckSolution> {
+    check_target(target_net_pay)?;
+
+    // Below this the pre-tax deductions are more than the paycheck
+    let periods = paycheck_info.pay_frequency.periods_per_year() as f64;
+    let low = (paycheck_info.pre_tax_deductions * periods).ceil().max(0.0) as u64;
+    let (gross_yearly_income, paycheck) = search_gross_income(
+        low,
+        target_net_pay,
+        |gross_yearly_income| {
+            let info = TaxInfo {
+                gross_yearly_income,
+                ..input_info.clone()
+            };
+            calculate_paycheck(&info, paycheck_info)
+        },
+        PaycheckWithholding::net_pay,
+    )?;
+    Ok(PaycheckSolution {
+        target_net_pay,
+        pay_frequency: paycheck_info.pay_frequency,
+        gross_yearly_income,
+        paycheck,
+    })
+}
+
+/// Finds the smallest gross yearly income, from `low` up, whose net amount reaches the target.
+/// The net amount must never shrink as the gross income grows.
+fn search_gross_income<R>(
+    mut low: u64,
+    target: f64,
+    calculate: impl Fn(u64) -> EstimaterResult<R>,
+    net: impl Fn(&R) -> f64,
+) -> EstimaterResult<(u64, R)> {
+    let low_result = calculate(low)?;
+    if net(&low_result) >= target {
+        return Ok((low, low_result));
+    }
+
+    // Double the gross income until it's enough, then narrow in on the smallest one that is
+    let mut high = low.max(1);
+    let mut high_result = calculate(high)?;
+    while net(&high_result) < target {
+        if high >= MAX_GROSS_YEARLY_INCOME {
+            return Err(EstimaterErrors::UserError(format!(
+                "No gross income up to {MAX_GROSS_YEARLY_INCOME} reaches a net amount of {target}"
+            )));
+        }
+        low = high;
+        high = (high * 2).min(MAX_GROSS_YEARLY_INCOME);
+        high_result = calculate(high)?;
+    }
+
+    // Invariant: the net amount at `low` is below the target, and at `high` it is not
+    while high - low > 1 {
+        let middle = low + (high - low) / 2;
+        let middle_result = calculate(middle)?;
+        if net(&middle_result) >= target {
+            high = middle;
+            high_result = middle_result;
+        } else {
+            low = middle;
+        }
+    }
+    Ok((high, high_result))
+}
+
+/// Checks the target is an actual amount
+fn check_target(target: f64) -> EstimaterResult<()> {
+    if target.is_finite() {
+        Ok(())
+    } else {
+        Err(EstimaterErrors::UserError(format!(
+            "The target net amount must be a number, got {target}"
+        )))
+    }
+}
+
+/// Calculates the taxes with the gross yearly income replaced
+fn calculate_taxes_at(
+    input_info: &TaxInfo,
+    gross_yearly_income: u64,
+) -> EstimaterResult<TaxResults> {
+    calculate_taxes(&TaxInfo {
+        gross_yearly_income,
+        ..input_info.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::use_shipped_data;
+    use estimate_common::paycheck::PayFrequency;
+
+    fn help_make_info(state_tax_rate_percent: f64) -> TaxInfo {
+        TaxInfo {
+            state_tax_rate_percent,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_solve_gross_income() {
+        use_shipped_data();
+        // 100000 gross leaves 100000 - 17835.5 federal - 5000 state
+        let solution = solve_gross_income(&help_make_info(5.0), 77164.5)
+            .expect("Solving for the gross income should've worked");
+        assert_eq!(solution.gross_yearly_income, 100000);
+        assert_eq!(solution.results.net_income, 77164.5);
+
+        // One dollar less of gross income keeps only 71 cents of it in the 24% bracket, so a
+        // slightly lower target still needs the same gross income
+        let solution = solve_gross_income(&help_make_info(5.0), 77164.0)
+            .expect("Solving for the gross income should've worked");
+        assert_eq!(solution.gross_yearly_income, 100000);
+    }
+
+    #[test]
+    fn test_solve_gross_income_round_trips() {
+        use_shipped_data();
+        let mut info = help_make_info(4.25);
+        info.pre_tax_deducations = 20000.0;
+        info.bonus_income = 15000.0;
+        for gross_yearly_income in [5000, 48000, 250000, 1200000] {
+            let expected = calculate_taxes_at(&info, gross_yearly_income)
+                .expect("Tax calculation should've worked");
+            let solution = solve_gross_income(&info, expected.net_income)
+                .expect("Solving for the gross income should've worked");
+            assert_eq!(
+                solution.gross_yearly_income, gross_yearly_income,
+                "Expected the gross income that produced a net income of {}",
+                expected.net_income
+            );
+        }
+    }
+
+    #[test]
+    fn test_solve_gross_income_already_reached() {
+        use_shipped_data();
+        let mut info = help_make_info(5.0);
+        info.bonus_income = 50000.0;
+        let solution = solve_gross_income(&info, 10000.0)
+            .expect("Solving for the gross income should've worked");
+        assert_eq!(solution.gross_yearly_income, 0);
+
+        // Deductions beyond the bonus need enough gross income to cover them
+        info.pre_tax_deducations = 60000.0;
+        let solution =
+            solve_gross_income(&info, 0.0).expect("Solving for the gross income should've worked");
+        assert_eq!(solution.gross_yearly_income, 10000);
+    }
+
+    #[test]
+    fn test_solve_gross_from_paycheck() {
+        use_shipped_data();
+        let paycheck_info = PaycheckInfo {
+            pay_frequency: PayFrequency::Weekly,
+            pre_tax_deductions: 100.0,
+            post_tax_deductions: 50.0,
+        };
+        let expected = calculate_paycheck(
+            &TaxInfo {
+                gross_yearly_income: 52000,
+                ..help_make_info(5.0)
+            },
+            &paycheck_info,
+        )
+        .expect("Paycheck should've calculated");
+
+        let solution =
+            solve_gross_from_paycheck(&help_make_info(5.0), &paycheck_info, expected.net_pay())
+                .expect("Solving for the gross income should've worked");
+        assert_eq!(solution.gross_yearly_income, 52000);
+        assert_eq!(solution.paycheck.federal_withholding, 113.71);
+        assert_eq!(solution.paycheck.net_pay(), expected.net_pay());
+    }
+
+    #[test]
+    fn test_solve_gross_income_unreachable() {
+        use_shipped_data();
+        assert!(matches!(
+            solve_gross_income(&help_make_info(100.0), 50000.0),
+            Err(EstimaterErrors::UserError(_))
+        ));
+        assert!(matches!(
+            solve_gross_income(&help_make_info(5.0), f64::NAN),
+            Err(EstimaterErrors::UserError(_))
+        ));
+    }
+}
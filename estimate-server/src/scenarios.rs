@@ -0,0 +1,136 @@
+/// Runs a base scenario and named variants of it, to compare their taxes side by side.
+use std::collections::HashSet;
+
+use estimate_common::{
+    common::TaxInfo,
+    errors::{EstimaterErrors, EstimaterResult},
+    scenarios::{ScenarioComparison, ScenarioFile, ScenarioResult, BASE_SCENARIO_NAME},
+};
+use serde_json::{Map, Value};
+
+use crate::server::calculate_taxes;
+
+/// Calculates the taxes of the base scenario and each of its variants.
+///
+/// # Return
+///
+/// * `Error`: Some error explaining why a scenario could not be built or calculated
+/// * `Ok(ScenarioComparison)`: The taxes of every scenario, starting with the base
+pub fn compare_scenarios(scenario_file: &ScenarioFile) -> EstimaterResult<ScenarioComparison> {
+    let scenarios = expand_scenarios(scenario_file)?
+        .into_iter()
+        .map(|(name, tax_info)| {
+            let results = calculate_taxes(&tax_info).map_err(|err| {
+                EstimaterErrors::ServerError(format!(
+                    "Error calculating scenario \"{name}\": {err}"
+                ))
+            })?;
+            Ok(ScenarioResult { name, results })
+        })
+        .collect::<EstimaterResult<Vec<ScenarioResult>>>()?;
+    Ok(ScenarioComparison { scenarios })
+}
+
+/// Builds the `TaxInfo` of the base scenario and of each variant, by replacing the base's fields
+/// with the variant's.
+pub(crate) fn expand_scenarios(
+    scenario_file: &ScenarioFile,
+) -> EstimaterResult<Vec<(String, TaxInfo)>> {
+    let mut names = HashSet::from([BASE_SCENARIO_NAME]);
+    let mut scenarios = vec![(
+        BASE_SCENARIO_NAME.to_string(),
+        parse_scenario(BASE_SCENARIO_NAME, scenario_file.base.clone())?,
+    )];
+
+    for variant in scenario_file.variants.iter() {
+        if !names.insert(&variant.name) {
+            return Err(EstimaterErrors::UserError(format!(
+                "The scenario \"{}\" is defined more than once",
+                variant.name
+            )));
+        }
+        let mut fields = scenario_file.base.clone();
+        fields.extend(variant.overrides.clone());
+        scenarios.push((variant.name.clone(), parse_scenario(&variant.name, fields)?));
+    }
+    Ok(scenarios)
+}
+
+/// Converts the fields of a scenario into its `TaxInfo`
+fn parse_scenario(name: &str, fields: Map<String, Value>) -> EstimaterResult<TaxInfo> {
+    serde_json::from_value(Value::Object(fields)).map_err(|err| {
+        EstimaterErrors::ParsingError(format!("The scenario \"{name}\" is invalid: {err}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::use_shipped_data;
+
+    fn help_make_scenario_file() -> ScenarioFile {
+        serde_json::from_str(
+            r#"{
+                "base": {
+                    "federal_tax_rate_percent": 0,
+                    "gross_yearly_income": 100000,
+                    "pre_tax_deducations": 0,
+                    "state_tax_rate_percent": 5
+                },
+                "variants": [
+                    {"name": "max 401k", "pre_tax_deducations": 22500},
+                    {"name": "move to Texas", "state_tax_rate_percent": 0}
+                ]
+            }"#,
+        )
+        .expect("The scenario file should parse")
+    }
+
+    #[test]
+    fn test_expand_scenarios() {
+        let scenarios = expand_scenarios(&help_make_scenario_file())
+            .expect("Expanding the scenarios should've worked");
+
+        let names: Vec<&str> = scenarios.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["base", "max 401k", "move to Texas"]);
+        assert_eq!(scenarios[1].1.pre_tax_deducations, 22500.0);
+        assert_eq!(scenarios[1].1.state_tax_rate_percent, 5.0);
+        assert_eq!(scenarios[2].1.pre_tax_deducations, 0.0);
+        assert_eq!(scenarios[2].1.state_tax_rate_percent, 0.0);
+    }
+
+    #[test]
+    fn test_expand_scenarios_errors() {
+        let mut duplicate = help_make_scenario_file();
+        duplicate.variants[1].name = "max 401k".to_string();
+        assert!(matches!(
+            expand_scenarios(&duplicate),
+            Err(EstimaterErrors::UserError(_))
+        ));
+
+        let mut invalid = help_make_scenario_file();
+        invalid.variants[0]
+            .overrides
+            .insert("gross_yearly_income".to_string(), Value::from("a lot"));
+        assert!(matches!(
+            expand_scenarios(&invalid),
+            Err(EstimaterErrors::ParsingError(_))
+        ));
+    }
+
+    #[test]
+    fn test_compare_scenarios() {
+        use_shipped_data();
+        let comparison = compare_scenarios(&help_make_scenario_file())
+            .expect("Comparing the scenarios should've worked");
+
+        let base = &comparison.scenarios[0];
+        assert_eq!(base.results.federal_tax, 17835.5);
+        assert_eq!(comparison.net_income_change(base), 0.0);
+        // Moving to a state without income tax saves the 5% state tax
+        let texas = &comparison.scenarios[2];
+        assert_eq!(comparison.net_income_change(texas), 5000.0);
+        assert_eq!(texas.income(), 100000.0);
+    }
+}
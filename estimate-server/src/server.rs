@@ -1,27 +1,69 @@
 /// Implement a server to do the "hard" work relating to calculating the taxes.
-use std::env;
-use std::io;
-use std::path::PathBuf;
-
 use estimate_common::{
-    common::{TaxInfo, TaxResults},
+    common::{BracketBreakdown, FilingStatus, TaxInfo, TaxResults},
     errors::{EstimaterErrors, EstimaterResult},
 };
 
-use crate::tax_bracket::TaxBrackets;
+use crate::{
+    benefits::calculate_compensation_results,
+    employment::calculate_employment_results,
+    equity::{calculate_equity_dispositions, calculate_rsu_results},
+    standard_deduction::StandardDeductions,
+    supplemental::calculate_supplemental_wage_results,
+    tax_bracket::TaxBrackets,
+    utils::round_to_hundredths,
+};
 
-/// Calculates the taxes that will be levied for the given input
+/// Calculates the taxes that will be levied for the given input. No standard deduction is
+/// taken.
 ///
 /// # Return
 ///
 /// * `Error`: Some error explaining why the calculation could not be completed
 /// * `Ok(TaxResults)`: A breakdown of the taxes paid and the net income result
 pub fn calculate_taxes(input_info: &TaxInfo) -> EstimaterResult<TaxResults> {
-    let intermediate = IntermediateTaxData::new(input_info);
+    let bracket_file = get_federal_bracket_file(&input_info.filing_status);
+    calculate_taxes_with_brackets(input_info, &TaxBrackets::from_data_file(bracket_file)?, 0.0)
+}
+
+/// Calculates the taxes that will be levied for the given input, with the federal taxable
+/// income lowered by the standard deduction of its filing status
+///
+/// # Return
+///
+/// * `Error`: Some error explaining why the calculation could not be completed
+/// * `Ok(TaxResults)`: A breakdown of the taxes paid and the net income result
+pub fn calculate_taxes_with_standard_deduction(
+    input_info: &TaxInfo,
+) -> EstimaterResult<TaxResults> {
+    let bracket_file = get_federal_bracket_file(&input_info.filing_status);
+    let standard_deduction =
+        StandardDeductions::from_data_file()?.for_filing_status(&input_info.filing_status);
+    calculate_taxes_with_brackets(
+        input_info,
+        &TaxBrackets::from_data_file(bracket_file)?,
+        standard_deduction,
+    )
+}
+
+/// Calculates the taxes that will be levied for the given input, with the given federal
+/// brackets instead of the ones of its filing status
+///
+/// # Params
+/// * `standard_deduction` - Taken off the federal taxable income, but never below 0
+pub(crate) fn calculate_taxes_with_brackets(
+    input_info: &TaxInfo,
+    tax_bracket: &TaxBrackets,
+    standard_deduction: f64,
+) -> EstimaterResult<TaxResults> {
+    let equity_dispositions =
+        calculate_equity_dispositions(&input_info.espp_sales, &input_info.iso_exercises)?;
+    let intermediate = IntermediateTaxData::new(
+        input_info,
+        equity_dispositions.total_ordinary_income(),
+        standard_deduction,
+    );
 
-    // TODO: Add path to json file as part of Client CLI input / what is passed to server
-    let tax_bracket =
-        TaxBrackets::from_bracket_json(get_path_to_data("federal_tax_bracket.json").unwrap())?;
     let federal_tax = match tax_bracket.calculate_tax_amount(intermediate.taxable_income) {
         Err(err) => Err(EstimaterErrors::ServerError(format!(
             "Error calculating federal taxes: {err}"
@@ -29,50 +71,163 @@ pub fn calculate_taxes(input_info: &TaxInfo) -> EstimaterResult<TaxResults> {
         Ok(tax) => tax,
     };
 
-    let state_tax = intermediate.taxable_income * (input_info.state_tax_rate_percent / 100.0);
-    let net_income = (input_info.gross_yearly_income as f64) - federal_tax - state_tax;
-    Ok(TaxResults::new(federal_tax, state_tax, net_income))
+    let state_tax = intermediate.state_taxable_income * (input_info.state_tax_rate_percent / 100.0);
+    let net_income = intermediate.income - federal_tax - state_tax;
+    let mut results = TaxResults::new(
+        federal_tax,
+        state_tax,
+        net_income,
+        input_info.total_federal_withholding(),
+        input_info.total_state_withholding(),
+    );
+
+    if input_info.bonus_income > 0.0 {
+        // The vests are compared on their own below, so the bonus is taxed beneath them
+        results.supplemental_wages = Some(calculate_supplemental_wage_results(
+            tax_bracket,
+            intermediate.taxable_income - input_info.rsu_income(),
+            input_info.bonus_income,
+        )?);
+    }
+    if !input_info.rsu_vests.is_empty() {
+        results.rsu_vests = Some(calculate_rsu_results(
+            tax_bracket,
+            intermediate.taxable_income,
+            input_info.bonus_income,
+            &input_info.rsu_vests,
+        )?);
+    }
+    if !input_info.espp_sales.is_empty() || !input_info.iso_exercises.is_empty() {
+        results.equity_dispositions = Some(equity_dispositions);
+    }
+    let yearly_hours = input_info.hourly.yearly_hours();
+    let is_only_hourly = input_info.hourly.yearly_wages() == intermediate.income;
+    if !input_info.hourly.is_unset() && is_only_hourly && yearly_hours > 0.0 {
+        results.after_tax_hourly_rate = Some(round_to_hundredths(net_income / yearly_hours));
+    }
+    if !input_info.employments.is_empty() {
+        results.employments = Some(calculate_employment_results(&input_info.employments)?);
+    }
+    if !input_info.benefits.is_unset() {
+        results.compensation = Some(calculate_compensation_results(input_info, net_income)?);
+    }
+    Ok(results)
+}
+
+/// Splits the federal taxable income for the given input across the federal brackets
+///
+/// # Return
+///
+/// * `Error`: Some error explaining why the calculation could not be completed
+/// * `Ok(Vec<BracketBreakdown>)`: The income and tax within each bracket, lowest first
+pub fn calculate_federal_bracket_breakdown(
+    input_info: &TaxInfo,
+) -> EstimaterResult<Vec<BracketBreakdown>> {
+    calculate_federal_bracket_breakdown_after(input_info, 0.0)
+}
+
+/// Splits the federal taxable income for the given input across the federal brackets, with the
+/// taxable income lowered by the standard deduction of its filing status. Matches
+/// `calculate_taxes_with_standard_deduction`.
+pub fn calculate_federal_bracket_breakdown_with_standard_deduction(
+    input_info: &TaxInfo,
+) -> EstimaterResult<Vec<BracketBreakdown>> {
+    let standard_deduction =
+        StandardDeductions::from_data_file()?.for_filing_status(&input_info.filing_status);
+    calculate_federal_bracket_breakdown_after(input_info, standard_deduction)
+}
+
+/// Splits the federal taxable income, after the given standard deduction, across the brackets
+fn calculate_federal_bracket_breakdown_after(
+    input_info: &TaxInfo,
+    standard_deduction: f64,
+) -> EstimaterResult<Vec<BracketBreakdown>> {
+    let equity_dispositions =
+        calculate_equity_dispositions(&input_info.espp_sales, &input_info.iso_exercises)?;
+    let intermediate = IntermediateTaxData::new(
+        input_info,
+        equity_dispositions.total_ordinary_income(),
+        standard_deduction,
+    );
+    let bracket_file = get_federal_bracket_file(&input_info.filing_status);
+    TaxBrackets::from_data_file(bracket_file)?.calculate_breakdown(intermediate.taxable_income)
 }
 
 /// Represents data / results generated mid calculation that get reused.
 struct IntermediateTaxData {
+    /// All wages, including ordinary income from equity sales
+    income: f64,
+    /// Income subject to federal income tax, after the pre-tax deductions and any standard
+    /// deduction
     taxable_income: f64,
+    /// Income subject to state income tax, which may include deductions the state taxes
+    state_taxable_income: f64,
 }
 
 impl IntermediateTaxData {
-    pub(crate) fn new(input_info: &TaxInfo) -> Self {
-        let taxable_income = input_info.gross_yearly_income as f64 - input_info.pre_tax_deducations;
-        Self { taxable_income }
+    pub(crate) fn new(
+        input_info: &TaxInfo,
+        equity_ordinary_income: f64,
+        standard_deduction: f64,
+    ) -> Self {
+        let income = input_info.total_wages() + equity_ordinary_income;
+        let adjusted_gross_income = income - input_info.total_pre_tax_deductions();
+        Self {
+            income,
+            taxable_income: adjusted_gross_income
+                - standard_deduction.clamp(0.0, adjusted_gross_income.max(0.0)),
+            state_taxable_income: income - input_info.state_pre_tax_deductions(),
+        }
     }
 }
 
-/// Gets the path to a given data file relative to project root based on the cwd.
-fn get_path_to_data(file_name: &str) -> io::Result<PathBuf> {
-    let server_crate_path = env::current_dir()?;
-    let project_root = server_crate_path
-        .parent()
-        .map_or_else(|| server_crate_path.as_path(), |root_path| root_path);
-    let data_dir = project_root;
-    let file_name = file_name.to_string();
-    Ok(data_dir.join("data").join(file_name))
+/// Gets the name of the federal bracket data file for a given filing status.
+pub(crate) fn get_federal_bracket_file(filing_status: &FilingStatus) -> &'static str {
+    match filing_status {
+        FilingStatus::Single => "federal_tax_bracket.json",
+        FilingStatus::MarriedFilingJointly => "federal_tax_bracket_married_jointly.json",
+        FilingStatus::MarriedFilingSeparately => "federal_tax_bracket_married_separately.json",
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::data::{get_path_to_shipped_data, use_shipped_data};
+    use chrono::NaiveDate;
+    use estimate_common::common::HourlyWage;
+    use estimate_common::deductions::{DeductionType, PreTaxDeduction};
+    use estimate_common::employment::Employment;
+    use estimate_common::equity::RsuVest;
 
     #[test]
     fn test_deserializing() {
-        // let path = "data/federal_tax_bracket.json";
-        let path = get_path_to_data("federal_tax_bracket.json");
-        assert!(path.is_ok(), "Path should be ok, but is {:?}", path.err());
-        let data = TaxBrackets::from_bracket_json(path.unwrap());
+        let path = get_path_to_shipped_data("federal_tax_bracket.json");
+        let data = TaxBrackets::from_bracket_file(path);
         assert!(data.is_ok(), "data should be ok, but is {:?}", data.err())
     }
 
+    #[test]
+    fn test_deserializing_all_filing_statuses() {
+        use_shipped_data();
+        for filing_status in [
+            FilingStatus::Single,
+            FilingStatus::MarriedFilingJointly,
+            FilingStatus::MarriedFilingSeparately,
+        ] {
+            let data = TaxBrackets::from_data_file(get_federal_bracket_file(&filing_status));
+            assert!(
+                data.is_ok(),
+                "{filing_status} brackets should be ok, but are {:?}",
+                data.err()
+            )
+        }
+    }
+
     #[test]
     fn test_calculate_taxes() {
+        use_shipped_data();
         // TODO: remove federal and state tax % once the API is updated to relfect the change in
         // server implementation.
         let test_input_info = TaxInfo {
@@ -80,6 +235,7 @@ mod tests {
             federal_tax_rate_percent: 0.0,
             state_tax_rate_percent: 5.0,
             pre_tax_deducations: 0.0,
+            ..Default::default()
         };
 
         let calculate_res =
@@ -101,6 +257,7 @@ mod tests {
             federal_tax_rate_percent: 0.0,
             state_tax_rate_percent: 5.0,
             pre_tax_deducations: 0.0,
+            ..Default::default()
         };
         let calculate_res =
             calculate_taxes(&test_input_info2).expect("Tax calculation should've worked");
@@ -111,4 +268,284 @@ mod tests {
             calculate_res.federal_tax
         );
     }
+
+    #[test]
+    fn test_calculate_taxes_typed_deductions() {
+        use_shipped_data();
+        let mut info = TaxInfo {
+            gross_yearly_income: 50000,
+            state_tax_rate_percent: 5.0,
+            deductions: vec![PreTaxDeduction::new(DeductionType::Hsa, 10000.0)],
+            ..Default::default()
+        };
+        let results = calculate_taxes(&info).expect("Tax calculation should've worked");
+        // Taxed on 40000 by both
+        assert_eq!(results.federal_tax, 4594.5);
+        assert_eq!(results.state_tax, 2000.0);
+
+        // A state that taxes HSA contributions still taxes all 50000
+        info.deductions[0].state_taxed = true;
+        let results = calculate_taxes(&info).expect("Tax calculation should've worked");
+        assert_eq!(results.federal_tax, 4594.5);
+        assert_eq!(results.state_tax, 2500.0);
+
+        // The untyped deductions are taxed like a deduction of type other
+        let untyped = TaxInfo {
+            gross_yearly_income: 50000,
+            pre_tax_deducations: 10000.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            untyped.pre_tax_deductions(),
+            vec![PreTaxDeduction::new(DeductionType::Other, 10000.0)]
+        );
+        // Social Security and Medicare are still taxed on all of it. Not -0 either, which
+        // would print as such
+        assert_eq!(untyped.fica_exempt_deductions(), 0.0);
+        assert!(untyped.fica_exempt_deductions().is_sign_positive());
+        assert_eq!(untyped.elective_deferrals(), 0.0);
+        let results = calculate_taxes(&untyped).expect("Tax calculation should've worked");
+        assert_eq!(results.federal_tax, 4594.5);
+    }
+
+    #[test]
+    fn test_calculate_taxes_with_standard_deduction() {
+        use_shipped_data();
+        let info = TaxInfo {
+            gross_yearly_income: 50000,
+            state_tax_rate_percent: 5.0,
+            ..Default::default()
+        };
+        let results = calculate_taxes_with_standard_deduction(&info)
+            .expect("Tax calculation should've worked");
+        // 1027.5 + 12% of (50000 - 12950 - 10275). The state does not take the deduction.
+        assert_eq!(results.federal_tax, 4240.5);
+        assert_eq!(results.state_tax, 2500.0);
+        assert_eq!(results.net_income, 50000.0 - 4240.5 - 2500.0);
+
+        // The deduction can not make the taxable income negative
+        let info = TaxInfo {
+            gross_yearly_income: 10000,
+            ..info
+        };
+        let results = calculate_taxes_with_standard_deduction(&info)
+            .expect("Tax calculation should've worked");
+        assert_eq!(results.federal_tax, 0.0);
+    }
+
+    #[test]
+    fn test_calculate_federal_bracket_breakdown() {
+        use_shipped_data();
+        let test_input_info = TaxInfo {
+            gross_yearly_income: 50000,
+            state_tax_rate_percent: 5.0,
+            ..Default::default()
+        };
+
+        let breakdown = calculate_federal_bracket_breakdown(&test_input_info)
+            .expect("Bracket breakdown should've worked");
+        let taxed_income: Vec<f64> = breakdown.iter().map(|b| b.taxed_income).collect();
+        assert_eq!(&taxed_income[..4], &[10275.0, 31500.0, 8225.0, 0.0]);
+        let total_tax: f64 = breakdown.iter().map(|b| b.tax).sum();
+        assert!(
+            total_tax == 6617.0,
+            "The bracket taxes should add up to the federal tax. Got: {}",
+            total_tax
+        );
+
+        let breakdown =
+            calculate_federal_bracket_breakdown_with_standard_deduction(&test_input_info)
+                .expect("Bracket breakdown should've worked");
+        let total_tax: f64 = breakdown.iter().map(|b| b.tax).sum();
+        let calculate_res = calculate_taxes_with_standard_deduction(&test_input_info)
+            .expect("Tax calculation should've worked");
+        assert_eq!(total_tax, calculate_res.federal_tax);
+    }
+
+    #[test]
+    fn test_calculate_refund() {
+        use_shipped_data();
+        let test_input_info = TaxInfo {
+            gross_yearly_income: 50000,
+            state_tax_rate_percent: 5.0,
+            federal_withholding_ytd: 5000.0,
+            federal_withholding_projected: 2000.0,
+            state_withholding_ytd: 2000.0,
+            ..Default::default()
+        };
+
+        let calculate_res =
+            calculate_taxes(&test_input_info).expect("Tax calculation should've worked");
+        assert!(
+            calculate_res.federal_refund() == 383.0,
+            "Federal Refund Expected: 383.0. Got: {}",
+            calculate_res.federal_refund()
+        );
+        assert!(
+            calculate_res.state_refund() == -500.0,
+            "State Refund Expected: -500.0. Got: {}",
+            calculate_res.state_refund()
+        );
+        let output = calculate_res.to_string();
+        assert!(output.contains("Federal Refund: 383"), "Got: {output}");
+        assert!(output.contains("State Amount Owed: 500"), "Got: {output}");
+
+        // Without any withholding there is no refund to report
+        let test_input_info = TaxInfo {
+            gross_yearly_income: 50000,
+            state_tax_rate_percent: 5.0,
+            ..Default::default()
+        };
+        let output = calculate_taxes(&test_input_info)
+            .expect("Tax calculation should've worked")
+            .to_string();
+        assert!(!output.contains("Refund"), "Got: {output}");
+        assert!(!output.contains("Amount Owed"), "Got: {output}");
+    }
+
+    #[test]
+    fn test_calculate_refund_with_supplemental_wages() {
+        use_shipped_data();
+        let test_input_info = TaxInfo {
+            gross_yearly_income: 50000,
+            bonus_income: 10000.0,
+            federal_withholding_ytd: 5000.0,
+            ..Default::default()
+        };
+
+        let calculate_res =
+            calculate_taxes(&test_input_info).expect("Tax calculation should've worked");
+        // 4807.5 + 22% of (60000 - 41775), less the 5000 given and 22% of the bonus withheld
+        assert_eq!(calculate_res.federal_tax, 8817.0);
+        assert_eq!(calculate_res.supplemental_withholding(), 2200.0);
+        assert_eq!(calculate_res.federal_refund(), 5000.0 + 2200.0 - 8817.0);
+    }
+
+    #[test]
+    fn test_calculate_refund_with_rsu_vests() {
+        use_shipped_data();
+        let test_input_info = TaxInfo {
+            gross_yearly_income: 50000,
+            bonus_income: 10000.0,
+            rsu_vests: vec![RsuVest {
+                vest_date: NaiveDate::from_ymd_opt(2023, 3, 15).unwrap(),
+                shares: 100,
+                price: 100.0,
+            }],
+            federal_withholding_ytd: 5000.0,
+            ..Default::default()
+        };
+
+        let calculate_res =
+            calculate_taxes(&test_input_info).expect("Tax calculation should've worked");
+        // The bonus and the vest are each only reported in their own section
+        let supplemental_wages = calculate_res
+            .supplemental_wages
+            .as_ref()
+            .expect("Supplemental wage results should be set");
+        assert_eq!(supplemental_wages.withheld, 2200.0);
+        assert_eq!(supplemental_wages.incremental_tax, 2200.0);
+        let rsu_vests = calculate_res
+            .rsu_vests
+            .as_ref()
+            .expect("RSU results should be set");
+        assert_eq!(rsu_vests.total_withheld(), 2200.0);
+        assert_eq!(rsu_vests.incremental_tax, 2200.0);
+
+        // 4807.5 + 22% of (70000 - 41775), less the 5000 given and both flat rate withholdings
+        assert_eq!(calculate_res.federal_tax, 11017.0);
+        assert_eq!(calculate_res.supplemental_withholding(), 4400.0);
+        assert_eq!(calculate_res.federal_refund(), 5000.0 + 4400.0 - 11017.0);
+    }
+
+    #[test]
+    fn test_calculate_taxes_with_employments() {
+        use_shipped_data();
+        let employment = |employer: &str, start_month, end_month, wages| Employment {
+            employer: employer.to_string(),
+            start_date: NaiveDate::from_ymd_opt(2023, start_month, 1).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2023, end_month, 28).unwrap(),
+            wages,
+            federal_withholding: 3500.0,
+            state_withholding: 1000.0,
+            social_security_withheld: None,
+            medicare_withheld: None,
+        };
+        let test_input_info = TaxInfo {
+            state_tax_rate_percent: 5.0,
+            employments: vec![
+                employment("Old Job", 1, 6, 30000.0),
+                employment("New Job", 7, 12, 20000.0),
+            ],
+            ..Default::default()
+        };
+
+        // The same taxes as a single job paying 50000, with the withholding of both
+        let calculate_res =
+            calculate_taxes(&test_input_info).expect("Tax calculation should've worked");
+        assert_eq!(calculate_res.federal_tax, 6617.0);
+        assert_eq!(calculate_res.federal_refund(), 383.0);
+        assert_eq!(calculate_res.state_refund(), -500.0);
+        let employments = calculate_res
+            .employments
+            .expect("Employment results should be set");
+        assert_eq!(employments.employers.len(), 2);
+        assert_eq!(employments.excess_social_security, 0.0);
+
+        // With the excess credit added, the refund is still to the cent
+        let mut test_input_info = test_input_info;
+        test_input_info.employments = vec![
+            employment("Old Job", 1, 5, 95000.0),
+            employment("New Job", 6, 12, 120000.0),
+        ];
+        test_input_info.employments[0].federal_withholding = 18000.0;
+        test_input_info.employments[1].federal_withholding = 24000.0;
+        let calculate_res =
+            calculate_taxes(&test_input_info).expect("Tax calculation should've worked");
+        assert_eq!(calculate_res.excess_social_security(), 3397.6);
+        assert_eq!(calculate_res.federal_refund(), -3633.9);
+    }
+
+    #[test]
+    fn test_calculate_taxes_hourly() {
+        use_shipped_data();
+        let test_input_info = TaxInfo {
+            state_tax_rate_percent: 5.0,
+            hourly: HourlyWage {
+                hourly_rate: 20.0,
+                hours_per_week: 40.0,
+                weeks_per_year: 50.0,
+                overtime_hours_per_week: 10.0,
+                overtime_multiplier: 1.5,
+            },
+            ..Default::default()
+        };
+        // 800 regular and 300 overtime pay a week, over 50 weeks
+        assert_eq!(test_input_info.total_wages(), 55000.0);
+
+        let calculate_res =
+            calculate_taxes(&test_input_info).expect("Tax calculation should've worked");
+        // 4807.5 + 22% of (55000 - 41775)
+        assert_eq!(calculate_res.federal_tax, 7717.0);
+        assert_eq!(calculate_res.net_income, 55000.0 - 7717.0 - 2750.0);
+        // Over 2500 hours worked
+        assert_eq!(calculate_res.after_tax_hourly_rate, Some(17.81));
+
+        let salaried = calculate_taxes(&TaxInfo {
+            gross_yearly_income: 55000,
+            state_tax_rate_percent: 5.0,
+            ..Default::default()
+        })
+        .expect("Tax calculation should've worked");
+        assert_eq!(salaried.federal_tax, calculate_res.federal_tax);
+        assert_eq!(salaried.after_tax_hourly_rate, None);
+
+        // The net income of a bonus is not earned by the hour
+        let with_bonus = calculate_taxes(&TaxInfo {
+            bonus_income: 5000.0,
+            ..test_input_info
+        })
+        .expect("Tax calculation should've worked");
+        assert_eq!(with_bonus.after_tax_hourly_rate, None);
+    }
 }
@@ -0,0 +1,38 @@
+/// Reads the federal standard deduction of each filing status.
+use serde::Deserialize;
+
+use estimate_common::{common::FilingStatus, errors::EstimaterResult};
+
+use crate::data::read_data_file;
+
+/// The data file listing the standard deduction of each filing status
+const STANDARD_DEDUCTION_FILE: &str = "standard_deductions.json";
+
+/// Struct representing the standard deduction of each filing status.
+#[derive(Debug, Deserialize)]
+pub(crate) struct StandardDeductions {
+    single: f64,
+    married_filing_jointly: f64,
+    married_filing_separately: f64,
+}
+
+impl StandardDeductions {
+    /// Attempts to read the data file containing the standard deductions.
+    ///
+    /// # Return
+    ///
+    /// * Error if file doesn't exist (or something else)
+    /// * Success: StandardDeductions instance
+    pub(crate) fn from_data_file() -> EstimaterResult<Self> {
+        read_data_file(STANDARD_DEDUCTION_FILE)
+    }
+
+    /// Gets the standard deduction of a filing status
+    pub(crate) fn for_filing_status(&self, filing_status: &FilingStatus) -> f64 {
+        match filing_status {
+            FilingStatus::Single => self.single,
+            FilingStatus::MarriedFilingJointly => self.married_filing_jointly,
+            FilingStatus::MarriedFilingSeparately => self.married_filing_separately,
+        }
+    }
+}
@@ -0,0 +1,87 @@
+/// Compares the flat rate withholding on supplemental wages (i.e. bonuses) to the tax they
+/// actually cause.
+use estimate_common::{common::SupplementalWageResults, errors::EstimaterResult};
+
+use crate::{tax_bracket::TaxBrackets, utils::round_to_hundredths};
+
+/// Flat rate withheld from supplemental wages up to `MANDATORY_RATE_THRESHOLD`
+const SUPPLEMENTAL_RATE: f64 = 0.22;
+/// Flat rate that must be withheld from supplemental wages above `MANDATORY_RATE_THRESHOLD`
+const MANDATORY_SUPPLEMENTAL_RATE: f64 = 0.37;
+/// Supplemental wages within a year above which the mandatory rate applies
+const MANDATORY_RATE_THRESHOLD: f64 = 1_000_000.0;
+
+/// Calculates the withholding on supplemental wages and the federal tax they add.
+///
+/// # Params
+/// * `brackets` - The federal brackets of the filer
+/// * `taxable_income` - The taxable income, including the supplemental wages
+/// * `supplemental_wages` - The supplemental wages paid over the year
+pub(crate) fn calculate_supplemental_wage_results(
+    brackets: &TaxBrackets,
+    taxable_income: f64,
+    supplemental_wages: f64,
+) -> EstimaterResult<SupplementalWageResults> {
+    let tax_with_supplemental = brackets.calculate_tax_amount(taxable_income.max(0.0))?;
+    let tax_without_supplemental =
+        brackets.calculate_tax_amount((taxable_income - supplemental_wages).max(0.0))?;
+
+    Ok(SupplementalWageResults {
+        withheld: calculate_supplemental_withholding(supplemental_wages),
+        incremental_tax: round_to_hundredths(tax_with_supplemental - tax_without_supplemental),
+    })
+}
+
+/// Calculates the federal withholding on a year's supplemental wages using the flat rates.
+pub(crate) fn calculate_supplemental_withholding(supplemental_wages: f64) -> f64 {
+    let below_threshold = supplemental_wages.min(MANDATORY_RATE_THRESHOLD);
+    let above_threshold = (supplemental_wages - MANDATORY_RATE_THRESHOLD).max(0.0);
+    round_to_hundredths(
+        below_threshold * SUPPLEMENTAL_RATE + above_threshold * MANDATORY_SUPPLEMENTAL_RATE,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::get_path_to_shipped_data;
+
+    fn help_load_brackets() -> TaxBrackets {
+        TaxBrackets::from_bracket_file(get_path_to_shipped_data("federal_tax_bracket.json"))
+            .expect("Brackets should load")
+    }
+
+    #[test]
+    fn test_calculate_supplemental_withholding() {
+        assert_eq!(calculate_supplemental_withholding(0.0), 0.0);
+        assert_eq!(calculate_supplemental_withholding(10000.0), 2200.0);
+        assert_eq!(calculate_supplemental_withholding(1_500_000.0), 405000.0);
+    }
+
+    #[test]
+    fn test_calculate_supplemental_wage_results() {
+        let brackets = help_load_brackets();
+
+        let results = calculate_supplemental_wage_results(&brackets, 60000.0, 10000.0)
+            .expect("Calculating supplemental wages should've worked");
+        assert!(
+            results.withholding_gap() == 0.0,
+            "Bonus within the 22% bracket should be withheld exactly. Got: {}",
+            results.withholding_gap()
+        );
+
+        let results = calculate_supplemental_wage_results(&brackets, 110000.0, 10000.0)
+            .expect("Calculating supplemental wages should've worked");
+        assert!(
+            results.incremental_tax == 2400.0,
+            "Expected: 2400.0. Got: {}",
+            results.incremental_tax
+        );
+        assert!(
+            results.withholding_gap() == -200.0,
+            "Expected: -200.0. Got: {}",
+            results.withholding_gap()
+        );
+    }
+}
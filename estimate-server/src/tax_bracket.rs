@@ -1,30 +1,41 @@
 /// Implements the concept of tax brackets. Usable for both state and federal
 /// income taxes.
-use serde::Deserialize;
+///
+/// Each bracket starts at a threshold and its rate applies to income above it, up to the
+/// threshold of the next bracket. The top bracket has no upper limit.
+use serde::{Deserialize, Serialize};
 use serde_valid::Validate;
-use std::{cmp::Ordering, fmt, fs::File, io::BufReader, path::PathBuf};
+use std::{cmp::Ordering, fmt};
 
-use estimate_common::errors::{BracketErrors, EstimaterErrors, EstimaterResult};
+use estimate_common::{
+    common::BracketBreakdown,
+    errors::{BracketErrors, EstimaterErrors, EstimaterResult},
+};
+
+use crate::{data::read_data_file, utils::round_to_hundredths};
 
 type BracketResult<T> = std::result::Result<T, BracketErrors>;
 
+/// How far a stated cumulative previous tax may be from the tabulated one, as the tabulated one
+/// is a sum of floats
+const CUMULATIVE_TAX_TOLERANCE: f64 = 0.005;
+
 /// Struct representing all tax brackets that exist.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct TaxBrackets {
     brackets: Vec<BracketInfo>,
 }
 
 /// Struct representing an individual tax bracket
-#[derive(Debug, Deserialize, Validate, Clone)]
+#[derive(Debug, Deserialize, Serialize, Validate, Clone)]
+#[serde(try_from = "RawBracketInfo")]
 pub(crate) struct BracketInfo {
-    /// The lower limit (inclusive) that this tax bracket is a part of.
-    /// CANNOT overlap with max of previous!
-    pub bracket_min: u64,
-    /// The lower limit (inclusive) that this tax bracket is part of
-    /// CANNOT overlap with min of next bracket!
-    pub bracket_max: u64,
+    /// The taxable income above which this bracket's rate applies. The bracket ends where the
+    /// next one starts.
+    #[validate(minimum = 0.0)]
+    pub threshold: f64,
     /// The percentage tax rate that is applied to the amount within this tax
-    /// bracket. i.e. this rate gets applied to `value` in `lower_limit` <= `value` < `upper_limit`.
+    /// bracket. i.e. this rate gets applied to `value` in `threshold` < `value` <= `next threshold`.
     /// Note: ranges 0 <= `tax_rate` <= 1
     #[validate(minimum = 0.0)]
     #[validate(maximum = 1.0)]
@@ -33,33 +44,103 @@ pub(crate) struct BracketInfo {
     /// The overall taxes paid through all the previous tax brackets (excluding this one).
     /// This is the total amount of taxes that are required by all brackets BEFORE
     /// this one.
+    /// Optional. When absent, it is computed from the brackets before this one.
     #[validate(minimum = 0.0)]
-    cumulative_previous_tax: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cumulative_previous_tax: Option<f64>,
+
+    /// The inclusive (min, max) the bracket was given with when written in the older
+    /// `bracket_min` / `bracket_max` format. Only used to check that those brackets line up.
+    #[serde(skip)]
+    pub(crate) legacy_bounds: Option<(u64, u64)>,
+}
+
+/// A bracket as written in a file, in either the threshold format or the older
+/// `bracket_min` / `bracket_max` format.
+#[derive(Deserialize)]
+struct RawBracketInfo {
+    threshold: Option<f64>,
+    bracket_min: Option<u64>,
+    bracket_max: Option<u64>,
+    tax_rate: f64,
+    #[serde(default)]
+    cumulative_previous_tax: Option<f64>,
+}
+
+impl TryFrom<RawBracketInfo> for BracketInfo {
+    type Error = String;
+
+    fn try_from(raw: RawBracketInfo) -> Result<Self, Self::Error> {
+        let (threshold, legacy_bounds) =
+            match (raw.threshold, raw.bracket_min, raw.bracket_max) {
+                (Some(threshold), None, None) => (threshold, None),
+                // An inclusive minimum of `bracket_min` is the same as applying above the dollar
+                // before it
+                (None, Some(bracket_min), Some(bracket_max)) => (
+                    bracket_min.saturating_sub(1) as f64,
+                    Some((bracket_min, bracket_max)),
+                ),
+                (Some(_), _, _) => {
+                    return Err(
+                        "A bracket must not have both a threshold and a bracket_min / bracket_max"
+                            .to_string(),
+                    )
+                }
+                _ => return Err(
+                    "A bracket must have either a threshold, or both a bracket_min and bracket_max"
+                        .to_string(),
+                ),
+            };
+        Ok(Self {
+            threshold,
+            tax_rate: raw.tax_rate,
+            cumulative_previous_tax: raw.cumulative_previous_tax,
+            legacy_bounds,
+        })
+    }
 }
 
 impl TaxBrackets {
-    /// Attempts to read from the json containing tax bracket info.
+    /// Attempts to read from the json, toml or yaml file (by its extension) containing tax
+    /// bracket info.
     ///
     /// # Return
     ///
-    /// * Error if file doesn't exist (or something else)
+    /// * Error if file doesn't exist or can't be parsed (or something else)
     /// * Success: TaxBracket instance with sorted tax brackets.
-    pub(crate) fn from_bracket_json(path: PathBuf) -> EstimaterResult<Self> {
-        let file = File::open(&path);
-        if let Ok(opened_file) = file {
-            let read_buffer = BufReader::new(opened_file);
-            let mut brackets: TaxBrackets = serde_json::from_reader(read_buffer)
-                .map_err(EstimaterErrors::SerdeDeserializeError)?;
-            brackets.sort_brackets();
-            brackets.tabulate_cumulative_taxes()?;
-            brackets.validate_all_brackets()?;
-            Ok(brackets)
-        } else {
-            Err(EstimaterErrors::FileError(format!(
-                "The file {:?} does not exist",
-                path
-            )))
-        }
+    pub(crate) fn from_bracket_file(path: std::path::PathBuf) -> EstimaterResult<Self> {
+        Self::prepare(Self::read_bracket_file(&path)?)
+    }
+
+    /// Reads a json, toml or yaml bracket file (by its extension) as written, without sorting,
+    /// tabulating or validating it.
+    pub(crate) fn read_bracket_file(path: &std::path::Path) -> EstimaterResult<Self> {
+        estimate_common::file_format::read_file(path)
+    }
+
+    /// Attempts to read tax bracket info from a data file. See `data::read_data_file`.
+    ///
+    /// # Return
+    ///
+    /// * Error if file doesn't exist or can't be parsed (or something else)
+    /// * Success: TaxBracket instance with sorted tax brackets.
+    pub(crate) fn from_data_file(file_name: &str) -> EstimaterResult<Self> {
+        Self::prepare(read_data_file(file_name)?)
+    }
+
+    /// Sorts, tabulates and validates freshly read brackets
+    fn prepare(mut brackets: TaxBrackets) -> EstimaterResult<Self> {
+        brackets.sort_brackets();
+        brackets.tabulate_cumulative_taxes()?;
+        brackets.validate_all_brackets()?;
+        Ok(brackets)
+    }
+
+    /// Whether any bracket was written in the older `bracket_min` / `bracket_max` format
+    pub(crate) fn has_legacy_brackets(&self) -> bool {
+        self.brackets
+            .iter()
+            .any(|bracket| bracket.legacy_bounds.is_some())
     }
 
     /// Resorts all brackets to be in the correct order
@@ -69,6 +150,8 @@ impl TaxBrackets {
 
     /// For all brackets, tabulates the cumulative amount of taxes across all
     /// previous brackets UP TO (but not including) this one.
+    /// Brackets without a cumulative amount get the tabulated one. Brackets with one are
+    /// checked against it.
     ///
     /// # Precondition
     /// The brackets are ordered by their bounds
@@ -77,56 +160,61 @@ impl TaxBrackets {
         let mut prev_bracket: Option<BracketInfo> = None;
 
         for bracket in self.brackets.iter_mut() {
-            let prev_bracket_max = bracket.calculate_prev_bracket_max(&prev_bracket);
-
-            match prev_bracket_max {
-                Err(_) => {
-                    panic!(
-                        "Calulating the tabulated amount for the bracket failed.\n{:?}",
-                        prev_bracket_max.err()
+            let prev_bracket_max = bracket.calculate_prev_bracket_max(&prev_bracket)?;
+
+            match bracket.cumulative_previous_tax {
+                None => bracket.cumulative_previous_tax = Some(prev_bracket_max),
+                Some(stated) if !cumulative_taxes_match(stated, prev_bracket_max) => {
+                    let err_msg = format!(
+                        "The tabulated max {} does not match the expected {}",
+                        prev_bracket_max, stated
                     );
+                    return Err(EstimaterErrors::BracketError(
+                        BracketErrors::CumulativeTaxError(err_msg),
+                    ));
                 }
-                Ok(prev_bracket_max) => {
-                    if prev_bracket_max != bracket.cumulative_previous_tax {
-                        let err_msg = format!(
-                            "The tabulated max {} does not match the expected {}",
-                            prev_bracket_max, bracket.cumulative_previous_tax
-                        );
-                        return Err(EstimaterErrors::ServerError(err_msg));
-                    } else {
-                        prev_bracket = Some(bracket.clone());
-                    }
-                }
+                Some(_) => {}
             }
+            prev_bracket = Some(bracket.clone());
         }
 
         Ok(())
     }
 
+    /// Overwrites the cumulative amount of taxes of every bracket with the tabulated one.
+    ///
+    /// # Precondition
+    /// The brackets are ordered by their bounds
+    pub(crate) fn fill_cumulative_taxes(&mut self) -> EstimaterResult<()> {
+        for bracket in self.brackets.iter_mut() {
+            bracket.cumulative_previous_tax = None;
+        }
+        self.tabulate_cumulative_taxes()
+    }
+
+    /// Moves every threshold up by a factor, rounded to the dollar, and retabulates the
+    /// cumulative taxes.
+    ///
+    /// # Precondition
+    /// The brackets are ordered by their bounds
+    pub(crate) fn inflate(&mut self, factor: f64) -> EstimaterResult<()> {
+        for bracket in self.brackets.iter_mut() {
+            bracket.threshold = (bracket.threshold * factor).round();
+            bracket.legacy_bounds = None;
+        }
+        self.fill_cumulative_taxes()
+    }
+
     /// # Pre-condition
     /// The brackets are sorted!
     pub(crate) fn validate_all_brackets(&self) -> EstimaterResult<()> {
         for (bracket_idx, bracket) in self.brackets.iter().enumerate() {
-            // BracketInfo::validate_new_bracket(bracket.bracket_min, bracket.bracket_max, bracket.tax_rate)
-            let val_res = BracketInfo::validate_new_bracket(
-                bracket.bracket_min,
-                bracket.bracket_max,
-                bracket.tax_rate,
-            );
-
-            if let Err(err) = val_res {
-                Err(EstimaterErrors::BracketError(err))?
-            }
-
-            if bracket_idx > 0 && bracket.check_for_bracket_overlap(&self.brackets[bracket_idx - 1])
-            {
-                let err_msg = format!(
-                    "Overlap of bracket {bracket} and {}",
-                    self.brackets[bracket_idx - 1]
-                );
-                Err(EstimaterErrors::BracketError(BracketErrors::OverlapError(
-                    err_msg,
-                )))?
+            bracket.validate_tax_rate()?;
+            bracket.validate_range()?;
+            if bracket_idx == 0 {
+                bracket.validate_lowest()?;
+            } else {
+                bracket.validate_follows(&self.brackets[bracket_idx - 1])?;
             }
         }
 
@@ -141,46 +229,69 @@ impl TaxBrackets {
     /// * `taxable_income` - The taxable income to apply the bracket too
     ///
     /// # Return
-    /// The amount to pay in taxes
+    /// The amount to pay in taxes. 0 when the taxable income is 0 or negative.
     pub(crate) fn calculate_tax_amount(&self, taxable_income: f64) -> EstimaterResult<f64> {
-        if taxable_income == 0.0 {
+        if taxable_income <= 0.0 {
             return Ok(0.0);
         }
 
         let tax_bracket_index = self.determine_correct_bracket(&taxable_income)?;
-        let bracket_info = &self.brackets[tax_bracket_index];
+        self.brackets[tax_bracket_index].calculate_bracket_taxes(taxable_income)
+    }
 
-        let prev_bracket: Option<BracketInfo> = if tax_bracket_index > 0 {
-            let prev_index = tax_bracket_index.wrapping_sub(1);
-            Some(self.brackets[prev_index].clone())
-        } else {
-            None
-        };
+    /// Splits a taxable income into the portion taxed by each bracket.
+    ///
+    /// # Return
+    /// Every bracket, with the income and tax within it. Brackets above the income have none,
+    /// as do all brackets when the income is negative.
+    pub(crate) fn calculate_breakdown(
+        &self,
+        taxable_income: f64,
+    ) -> EstimaterResult<Vec<BracketBreakdown>> {
+        let taxable_income = taxable_income.max(0.0);
+        self.determine_correct_bracket(&taxable_income)?;
+        let next_thresholds = self
+            .brackets
+            .iter()
+            .skip(1)
+            .map(|bracket| bracket.threshold)
+            .chain([f64::INFINITY]);
 
-        bracket_info.calculate_bracket_taxes(taxable_income, prev_bracket)
+        Ok(self
+            .brackets
+            .iter()
+            .zip(next_thresholds)
+            .map(|(bracket, next_threshold)| {
+                let taxed_income =
+                    (taxable_income.min(next_threshold) - bracket.threshold).max(0.0);
+                BracketBreakdown {
+                    threshold: bracket.threshold,
+                    tax_rate: bracket.tax_rate,
+                    taxed_income: round_to_hundredths(taxed_income),
+                    tax: round_to_hundredths(taxed_income * bracket.tax_rate),
+                }
+            })
+            .collect())
     }
 
-    /// Given a taxable income. Determines the correct top bracket to put it in.
+    /// Given a taxable income. Determines the correct top bracket to put it in. i.e. the highest
+    /// bracket whose threshold is below the income, or the lowest bracket if there is none.
     ///
     /// # Result
     /// * The bracket index if it exists
-    /// * `Err` - If the income does not have a valid bracket
+    /// * `Err` - If there are no brackets
     fn determine_correct_bracket(&self, taxable_income: &f64) -> BracketResult<usize> {
-        if taxable_income == &0.0 {
-            return Ok(0);
+        if self.brackets.is_empty() {
+            return Err(BracketErrors::LargeIncomeError(format!(
+                "The income {taxable_income} does not fit in ANY tax bracket"
+            )));
         }
 
-        self.brackets
+        Ok(self
+            .brackets
             .iter()
-            .position(|cur_bracket| {
-                taxable_income >= &(cur_bracket.bracket_min as f64)
-                    && taxable_income <= &(cur_bracket.bracket_max as f64)
-            })
-            .ok_or_else(|| {
-                BracketErrors::LargeIncomeError(format!(
-                    "The income {taxable_income} does not fit in ANY tax bracket"
-                ))
-            })
+            .rposition(|cur_bracket| taxable_income > &cur_bracket.threshold)
+            .unwrap_or(0))
     }
 }
 
@@ -197,14 +308,14 @@ impl fmt::Display for TaxBrackets {
 impl fmt::Display for BracketInfo {
     // This trait requires `fmt` with this exact signature.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "bracket_min = {}. ", self.bracket_min)?;
-        write!(f, "bracket_max = {}. ", self.bracket_max)?;
+        write!(f, "threshold = {}. ", self.threshold)?;
         write!(f, "tax_rate = {}. ", self.tax_rate)?;
-        write!(
-            f,
-            "cumulative_previous_tax = {}. ",
-            self.cumulative_previous_tax
-        )?;
+        match self.cumulative_previous_tax {
+            Some(cumulative_previous_tax) => {
+                write!(f, "cumulative_previous_tax = {}. ", cumulative_previous_tax)?
+            }
+            None => write!(f, "cumulative_previous_tax = unset. ")?,
+        }
         writeln!(f)
     }
 }
@@ -212,54 +323,99 @@ impl fmt::Display for BracketInfo {
 impl BracketInfo {
     #[allow(dead_code)]
     pub fn new(
-        bracket_min: u64,
-        bracket_max: u64,
+        threshold: f64,
         tax_rate: f64,
-        cumulative_previous_tax: f64,
+        cumulative_previous_tax: Option<f64>,
     ) -> EstimaterResult<Self> {
-        let validation_res = Self::validate_new_bracket(bracket_min, bracket_max, tax_rate);
-        match validation_res {
-            Err(err) => Err(EstimaterErrors::BracketError(err)),
-            Ok(_) => Ok(Self {
-                bracket_min,
-                bracket_max,
-                tax_rate,
-                cumulative_previous_tax,
-            }),
+        let bracket = Self {
+            threshold,
+            tax_rate,
+            cumulative_previous_tax,
+            legacy_bounds: None,
+        };
+        bracket.validate_tax_rate()?;
+        bracket.validate_range()?;
+        Ok(bracket)
+    }
+
+    /// The cumulative amount of taxes from all previous brackets. Always set once the brackets
+    /// are tabulated.
+    pub(crate) fn cumulative_tax(&self) -> f64 {
+        self.cumulative_previous_tax.unwrap_or(0.0)
+    }
+
+    /// Checks the tax rate is within [0, 1]
+    pub(crate) fn validate_tax_rate(&self) -> BracketResult<()> {
+        if !(0.0..=1.0).contains(&self.tax_rate) {
+            Err(BracketErrors::TaxRateError(format!(
+                "Tax rate {} not within [0, 1]",
+                self.tax_rate
+            )))
+        } else {
+            Ok(())
         }
     }
 
-    pub(crate) fn validate_new_bracket(
-        bracket_min: u64,
-        bracket_max: u64,
-        tax_rate: f64,
-    ) -> std::result::Result<(), BracketErrors> {
-        if !(0.0..=1.0).contains(&tax_rate) {
-            Err(BracketErrors::TaxRateError(
-                "Tax rate not within [0, 1]".to_string(),
-            ))
-        } else if bracket_min >= bracket_max {
-            let err_msg = format!(
-                "Bracket minimimum {} is >= bracket maximum ({})",
-                bracket_min, bracket_max
-            );
-            Err(BracketErrors::RangeError(err_msg))
+    /// Checks the threshold is a non-negative number, and that brackets in the older format have
+    /// a minimum below their maximum.
+    pub(crate) fn validate_range(&self) -> BracketResult<()> {
+        if !self.threshold.is_finite() || self.threshold < 0.0 {
+            return Err(BracketErrors::RangeError(format!(
+                "Threshold {} is not a non-negative number",
+                self.threshold
+            )));
+        }
+        match self.legacy_bounds {
+            Some((bracket_min, bracket_max)) if bracket_min >= bracket_max => {
+                Err(BracketErrors::RangeError(format!(
+                    "Bracket minimum {} is >= bracket maximum {}",
+                    bracket_min, bracket_max
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the lowest bracket starts at 0, so that every income is within a bracket.
+    pub(crate) fn validate_lowest(&self) -> BracketResult<()> {
+        if self.threshold != 0.0 {
+            Err(BracketErrors::GapError(format!(
+                "The lowest bracket must start at 0, not {}",
+                self.threshold
+            )))
         } else {
             Ok(())
         }
     }
 
-    /// Checks if there is overlap between 2 brackets from the perspective of self.
-    /// Overlap is found when:
-    /// The min of 1 bracket is between the min and max of another
-    /// OR
-    /// The max of 1 bracket is between the min and max of another
-    pub(crate) fn check_for_bracket_overlap(&self, other: &Self) -> bool {
-        let is_min_within_other: bool =
-            self.bracket_min >= other.bracket_min && self.bracket_min <= other.bracket_max;
-        let is_max_within_other: bool =
-            self.bracket_max >= other.bracket_min && self.bracket_max <= other.bracket_max;
-        is_min_within_other || is_max_within_other
+    /// Checks this bracket starts after the previous one, with nothing between them.
+    /// Brackets in the threshold format can only overlap by sharing a threshold. Brackets in the
+    /// older format must also have their minimum right after the previous maximum.
+    pub(crate) fn validate_follows(&self, previous_bracket: &Self) -> BracketResult<()> {
+        match (previous_bracket.legacy_bounds, self.legacy_bounds) {
+            (Some((_, previous_max)), Some((bracket_min, _))) => {
+                if bracket_min <= previous_max {
+                    Err(BracketErrors::OverlapError(format!(
+                        "Minimum {} overlaps the previous bracket which ends at {}",
+                        bracket_min, previous_max
+                    )))
+                } else if bracket_min > previous_max.saturating_add(1) {
+                    Err(BracketErrors::GapError(format!(
+                        "Minimum {} leaves a gap after the previous bracket which ends at {}",
+                        bracket_min, previous_max
+                    )))
+                } else {
+                    Ok(())
+                }
+            }
+            _ if self.threshold <= previous_bracket.threshold => {
+                Err(BracketErrors::OverlapError(format!(
+                    "Threshold {} overlaps the previous bracket which starts at {}",
+                    self.threshold, previous_bracket.threshold
+                )))
+            }
+            _ => Ok(()),
+        }
     }
 
     /// Calculates the taxes for the current bracket.
@@ -270,59 +426,72 @@ impl BracketInfo {
     /// # Return
     ///
     /// * The tax amount if successful
-    /// * `EstimaterErrors::BracketError` when the income is outside the bounds of taxable range
-    pub fn calculate_bracket_taxes(
-        &self,
-        taxable_income: f64,
-        previous_bracket: Option<Self>,
-    ) -> EstimaterResult<f64> {
-        let (current_bracket_tax, cumulative_previous_tax) = match previous_bracket {
-            None => {
-                let current_bracket_tax = self.tax_rate * taxable_income;
-                (current_bracket_tax, 0.0)
-            }
-            Some(prev_bracket) => {
-                let current_bracket_tax =
-                    self.tax_rate * (taxable_income - prev_bracket.bracket_max as f64);
-                (current_bracket_tax, self.cumulative_previous_tax)
-            }
-        };
-        let total_tax = current_bracket_tax + cumulative_previous_tax;
+    /// * `EstimaterErrors::BracketError` when the income is below the start of this bracket
+    pub fn calculate_bracket_taxes(&self, taxable_income: f64) -> EstimaterResult<f64> {
+        if taxable_income < self.threshold {
+            return Err(EstimaterErrors::BracketError(
+                BracketErrors::SmallIncomeError(format!(
+                    "The income {taxable_income} is below the bracket starting at {}",
+                    self.threshold
+                )),
+            ));
+        }
+        let current_bracket_tax = self.tax_rate * (taxable_income - self.threshold);
+        let total_tax = current_bracket_tax + self.cumulative_tax();
 
-        Ok(Self::round_to_hundredths(total_tax))
+        Ok(round_to_hundredths(total_tax))
     }
 
     /// Calculates the (tabulated) maximum tax resulting from this tax bracket. i.e. the graduated
     /// taxes from this bracket if it is exceeded.
     ///
     /// # General Formula
-    /// `cur.cum = prev.cum + ( (cur.min - prev.min) * prev.tax )`
+    /// `cur.cum = prev.cum + ( (cur.threshold - prev.threshold) * prev.tax )`
     ///
     /// # Return
     ///
     /// * 0 When the previous bracket doesnt exist
-    fn calculate_prev_bracket_max(&self, previous_bracket: &Option<Self>) -> EstimaterResult<f64> {
+    /// * `EstimaterErrors::BracketError` when the previous bracket does not start below this one
+    pub(crate) fn calculate_prev_bracket_max(
+        &self,
+        previous_bracket: &Option<Self>,
+    ) -> EstimaterResult<f64> {
         if let Some(previous_bracket) = previous_bracket {
-            let prev_bracket_width = self.bracket_min - previous_bracket.bracket_min;
+            let prev_bracket_width = self.threshold - previous_bracket.threshold;
+            if prev_bracket_width < 0.0 {
+                return Err(EstimaterErrors::BracketError(BracketErrors::OverlapError(
+                    format!("Bracket {self} starts before the previous bracket {previous_bracket}"),
+                )));
+            }
             let prev_bracket_max =
-                Self::round_to_hundredths(prev_bracket_width as f64 * previous_bracket.tax_rate);
-            let cur_cumulative = previous_bracket.cumulative_previous_tax + prev_bracket_max;
+                round_to_hundredths(prev_bracket_width * previous_bracket.tax_rate);
+            let cur_cumulative = previous_bracket.cumulative_tax() + prev_bracket_max;
             Ok(cur_cumulative)
         } else {
             Ok(0.0)
         }
     }
+}
 
-    /// A lot of tax documents only use 2 decimal sig-figs. To align our
-    /// calculations, the same is being repeated here.
-    pub(self) fn round_to_hundredths(value: f64) -> f64 {
-        (value * 100.0).round() / 100.0
-    }
+/// Whether a stated cumulative previous tax is the tabulated one, to within half a cent
+pub(crate) fn cumulative_taxes_match(stated: f64, tabulated: f64) -> bool {
+    (stated - tabulated).abs() <= CUMULATIVE_TAX_TOLERANCE
 }
 
+/// Brackets are ordered by where they start. Brackets sharing a threshold are ordered by their
+/// other fields, so that only equal brackets compare as equal.
 impl Ord for BracketInfo {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.bracket_min.cmp(&other.bracket_max)
+        let cumulative_tax_cmp = match (self.cumulative_previous_tax, other.cumulative_previous_tax)
+        {
+            (Some(tax), Some(other_tax)) => tax.total_cmp(&other_tax),
+            (tax, other_tax) => tax.is_some().cmp(&other_tax.is_some()),
+        };
+        self.threshold
+            .total_cmp(&other.threshold)
+            .then_with(|| self.tax_rate.total_cmp(&other.tax_rate))
+            .then(cumulative_tax_cmp)
+            .then_with(|| self.legacy_bounds.cmp(&other.legacy_bounds))
     }
 }
 
@@ -334,10 +503,7 @@ impl PartialOrd for BracketInfo {
 
 impl PartialEq for BracketInfo {
     fn eq(&self, other: &Self) -> bool {
-        self.bracket_min == other.bracket_min
-            && self.bracket_max == other.bracket_max
-            && self.tax_rate == other.tax_rate
-            && self.cumulative_previous_tax == other.cumulative_previous_tax
+        self.cmp(other) == Ordering::Equal
     }
 }
 
@@ -360,23 +526,14 @@ mod tests {
         ErrorType: std::fmt::Debug,
     {
         match result_to_check {
-            Err(err) => assert!(
-                false,
-                "{}",
-                format!(
-                    "Expected {expected_res}, got err: {:?} for {additional_fail_msg}",
-                    err
-                )
+            Err(err) => panic!(
+                "Expected {expected_res}, got err: {:?} for {additional_fail_msg}",
+                err
+            ),
+            Ok(res) => assert!(
+                res == expected_res,
+                "Expected {expected_res}, got {res} for {additional_fail_msg}"
             ),
-            Ok(res) => {
-                if res == expected_res {
-                    assert!(true)
-                } else {
-                    let err_msg =
-                        format!("Expected {expected_res}, got {res} for {additional_fail_msg}");
-                    assert!(false, "{err_msg}")
-                }
-            }
         }
     }
 
@@ -404,38 +561,34 @@ mod tests {
                 }
             ]
         }"#;
-        return serde_json::from_str(bracket_json_str).unwrap();
+        serde_json::from_str(bracket_json_str).unwrap()
     }
 
     #[test]
     fn test_calculate_prev_bracket_max() {
         let bracket1 = BracketInfo {
-            bracket_min: 1,
-            bracket_max: 10275,
+            threshold: 0.0,
             tax_rate: 0.1,
-            cumulative_previous_tax: 0.0,
+            cumulative_previous_tax: Some(0.0),
+            legacy_bounds: None,
         };
         let bracket2 = BracketInfo {
-            bracket_min: 10276,
-            bracket_max: 41775,
+            threshold: 10275.0,
             tax_rate: 0.12,
-            cumulative_previous_tax: 1027.5,
+            cumulative_previous_tax: Some(1027.5),
+            legacy_bounds: None,
         };
         let bracket3 = BracketInfo {
-            bracket_min: 41776,
-            bracket_max: 89075,
+            threshold: 41775.0,
             tax_rate: 0.22,
-            cumulative_previous_tax: 4807.50,
+            cumulative_previous_tax: Some(4807.50),
+            legacy_bounds: None,
         };
 
         let bracket1_res = bracket1.calculate_prev_bracket_max(&None);
-        let bracket1_cum_max = bracket1_res.as_ref().expect(
-            format!(
-                "calculate_prev_bracket_max failed for bracket1: {:?}",
-                bracket1_res
-            )
-            .as_str(),
-        );
+        let bracket1_cum_max = bracket1_res.as_ref().unwrap_or_else(|err| {
+            panic!("calculate_prev_bracket_max failed for bracket1: {:?}", err)
+        });
 
         assert!(
             bracket1_cum_max == &0.0,
@@ -445,30 +598,22 @@ mod tests {
         );
 
         let found_bracket2_res = bracket2.calculate_prev_bracket_max(&Some(bracket1));
-        let found_bracket2_cum_max = found_bracket2_res.as_ref().expect(
-            format!(
-                "calculate_prev_bracket_max failed for bracket2: {:?}",
-                found_bracket2_res
-            )
-            .as_str(),
-        );
+        let found_bracket2_cum_max = found_bracket2_res.as_ref().unwrap_or_else(|err| {
+            panic!("calculate_prev_bracket_max failed for bracket2: {:?}", err)
+        });
         assert!(
-            found_bracket2_cum_max == &bracket2.cumulative_previous_tax,
+            found_bracket2_cum_max == &bracket2.cumulative_tax(),
             "Bracket tabulated maximum incorrect. Expected: {:?}. Got: {:?}",
             1027.5,
             found_bracket2_cum_max
         );
 
         let found_bracket3_res = bracket3.calculate_prev_bracket_max(&Some(bracket2));
-        let found_bracket3_cum_max = found_bracket3_res.as_ref().expect(
-            format!(
-                "calculate_prev_bracket_max failed for bracket2: {:?}",
-                found_bracket2_res
-            )
-            .as_str(),
-        );
+        let found_bracket3_cum_max = found_bracket3_res.as_ref().unwrap_or_else(|err| {
+            panic!("calculate_prev_bracket_max failed for bracket2: {:?}", err)
+        });
         assert!(
-            found_bracket3_cum_max == &bracket3.cumulative_previous_tax,
+            found_bracket3_cum_max == &bracket3.cumulative_tax(),
             "Bracket tabulated maximum incorrect. Expected: {:?}. Got: {:?}",
             4807.50,
             found_bracket3_cum_max
@@ -564,4 +709,159 @@ mod tests {
             "input of 50000.0",
         );
     }
+
+    #[test]
+    fn test_tabulate_fills_missing_cumulative_taxes() {
+        let bracket_json_str = r#"{
+            "brackets": [
+                {"bracket_max": 10275, "bracket_min": 1, "tax_rate": 0.1},
+                {"bracket_max": 41775, "bracket_min": 10276, "tax_rate": 0.12},
+                {
+                    "bracket_max": 89075,
+                    "bracket_min": 41776,
+                    "cumulative_previous_tax": 4807.5,
+                    "tax_rate": 0.22
+                }
+            ]
+        }"#;
+        let mut brackets: TaxBrackets = serde_json::from_str(bracket_json_str).unwrap();
+        brackets
+            .tabulate_cumulative_taxes()
+            .expect("Missing cumulative taxes should be computed");
+        let cumulative: Vec<Option<f64>> = brackets
+            .brackets
+            .iter()
+            .map(|bracket| bracket.cumulative_previous_tax)
+            .collect();
+        assert_eq!(cumulative, vec![Some(0.0), Some(1027.5), Some(4807.5)]);
+
+        brackets.brackets[2].cumulative_previous_tax = Some(5000.0);
+        assert!(
+            brackets.tabulate_cumulative_taxes().is_err(),
+            "A stated cumulative tax that doesn't match should error"
+        );
+        brackets
+            .fill_cumulative_taxes()
+            .expect("Filling should overwrite the stated cumulative tax");
+        assert_eq!(brackets.brackets[2].cumulative_previous_tax, Some(4807.5));
+    }
+
+    #[test]
+    fn test_threshold_format() {
+        let bracket_json_str = r#"{
+            "brackets": [
+                {"threshold": 41775, "tax_rate": 0.22},
+                {"threshold": 0, "tax_rate": 0.1},
+                {"threshold": 10275, "tax_rate": 0.12}
+            ]
+        }"#;
+        let mut brackets: TaxBrackets = serde_json::from_str(bracket_json_str).unwrap();
+        brackets.sort_brackets();
+        brackets
+            .tabulate_cumulative_taxes()
+            .expect("Tabulating the threshold brackets should've worked");
+        brackets
+            .validate_all_brackets()
+            .expect("The threshold brackets should be valid");
+
+        // Matches the older format brackets for every income
+        let legacy_brackets = help_make_test_brackets();
+        for income in [0.0, 1000.0, 10275.0, 30000.0, 50000.0] {
+            assert_eq!(
+                brackets.calculate_tax_amount(income).unwrap(),
+                legacy_brackets.calculate_tax_amount(income).unwrap(),
+                "Income {income} should be taxed the same in both formats"
+            );
+        }
+    }
+
+    #[test]
+    fn test_calculate_fractional_and_unbounded_taxes() {
+        let brackets = help_make_test_brackets();
+        help_assert_result(brackets.calculate_tax_amount(0.5), 0.05, "input of 0.5");
+        help_assert_result(
+            brackets.calculate_tax_amount(10275.5),
+            1027.56,
+            "input of 10275.5",
+        );
+        // Above the maximum of the top bracket in the older format
+        help_assert_result(
+            brackets.calculate_tax_amount(100000.0),
+            17617.0,
+            "input of 100000.0",
+        );
+        help_assert_result(brackets.calculate_tax_amount(-1.0), 0.0, "input of -1.0");
+        let breakdown = brackets
+            .calculate_breakdown(-1.0)
+            .expect("A negative income should break down");
+        assert!(breakdown.iter().all(|bracket| bracket.tax == 0.0));
+    }
+
+    #[test]
+    fn test_stated_cumulative_taxes_allow_rounding() {
+        // The tabulated cumulative tax of the third bracket is 0.1 + 0.2, which is not exactly 0.3
+        let bracket_json_str = r#"{
+            "brackets": [
+                {"threshold": 0, "tax_rate": 0.1, "cumulative_previous_tax": 0.0},
+                {"threshold": 1, "tax_rate": 0.2, "cumulative_previous_tax": 0.1},
+                {"threshold": 2, "tax_rate": 0.3, "cumulative_previous_tax": 0.3}
+            ]
+        }"#;
+        let mut brackets: TaxBrackets = serde_json::from_str(bracket_json_str).unwrap();
+        assert!(brackets.tabulate_cumulative_taxes().is_ok());
+
+        brackets.brackets[2].cumulative_previous_tax = Some(0.31);
+        assert!(
+            brackets.tabulate_cumulative_taxes().is_err(),
+            "A cumulative tax a cent off should error"
+        );
+    }
+
+    #[test]
+    fn test_ordering_matches_equality() {
+        let bracket = BracketInfo::new(10275.0, 0.12, Some(1027.5)).unwrap();
+        let same = BracketInfo::new(10275.0, 0.12, Some(1027.5)).unwrap();
+        let other_rate = BracketInfo::new(10275.0, 0.22, Some(1027.5)).unwrap();
+        let unset_cumulative = BracketInfo::new(10275.0, 0.12, None).unwrap();
+
+        assert_eq!(bracket.cmp(&same), Ordering::Equal);
+        assert!(bracket == same);
+        for different in [&other_rate, &unset_cumulative] {
+            assert_ne!(bracket.cmp(different), Ordering::Equal);
+            assert!(bracket != *different);
+        }
+        assert!(bracket < other_rate, "Shared thresholds order by rate");
+    }
+
+    #[test]
+    fn test_validate_bracket_edges() {
+        let gap_bracket_json_str = r#"{
+            "brackets": [
+                {"bracket_max": 10000, "bracket_min": 1, "tax_rate": 0.1},
+                {"bracket_max": 40000, "bracket_min": 10500, "tax_rate": 0.12}
+            ]
+        }"#;
+        let gap_brackets: TaxBrackets = serde_json::from_str(gap_bracket_json_str).unwrap();
+        assert!(
+            matches!(
+                gap_brackets.validate_all_brackets(),
+                Err(EstimaterErrors::BracketError(BracketErrors::GapError(_)))
+            ),
+            "A gap between older format brackets should error"
+        );
+
+        let raised_bracket_json_str = r#"{"brackets": [{"threshold": 500, "tax_rate": 0.1}]}"#;
+        let raised_brackets: TaxBrackets = serde_json::from_str(raised_bracket_json_str).unwrap();
+        assert!(
+            raised_brackets.validate_all_brackets().is_err(),
+            "A lowest bracket above 0 should error"
+        );
+
+        let mixed_bracket_json_str =
+            r#"{"brackets": [{"threshold": 0, "bracket_min": 1, "tax_rate": 0.1}]}"#;
+        assert!(
+            serde_json::from_str::<TaxBrackets>(mixed_bracket_json_str).is_err(),
+            "A bracket in both formats should not parse"
+        );
+    }
 }
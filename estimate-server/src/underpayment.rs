@@ -0,0 +1,330 @@
+/// Estimates the penalty for underpaying taxes over the year, in the style of IRS Form 2210.
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Deserialize;
+
+use estimate_common::{
+    common::TaxInfo,
+    errors::{EstimaterErrors, EstimaterResult},
+    estimated_payments::{QuarterUnderpayment, UnderpaymentInfo, UnderpaymentPenalty},
+};
+
+use crate::{
+    data::read_data_file, estimated_payments::get_installment_due_dates,
+    server::calculate_taxes_with_standard_deduction, utils::round_to_hundredths,
+};
+
+/// The data file listing the interest rates charged on underpayments
+const INTEREST_RATE_FILE: &str = "underpayment_interest_rates.json";
+/// When the tax owed after withholding is below this, no penalty is charged.
+const MINIMUM_AMOUNT_OWED: f64 = 1000.0;
+
+/// Struct representing the interest rates charged on underpayments over time.
+#[derive(Debug, Deserialize)]
+pub(crate) struct InterestRateTable {
+    rates: Vec<InterestRate>,
+}
+
+/// An interest rate that applies from its start date until the start of the next rate.
+#[derive(Debug, Deserialize, Clone)]
+pub(crate) struct InterestRate {
+    pub start_date: NaiveDate,
+    /// The yearly rate as a fraction. i.e. 0.05 for 5%
+    pub annual_rate: f64,
+}
+
+/// A portion of an installment that is still unpaid
+struct OutstandingUnderpayment {
+    quarter_index: usize,
+    amount: f64,
+    since: NaiveDate,
+}
+
+/// Estimates the penalty for underpaying each quarterly installment.
+///
+/// # Params
+/// * `input_info` - The year's tax info. Its tax is taken after the standard deduction.
+/// * `underpayment_info` - The required annual payment and the payments actually made
+///
+/// # Return
+///
+/// * `Error`: Some error explaining why the calculation could not be completed
+/// * `Ok(UnderpaymentPenalty)`: The underpayment and penalty of each quarter
+pub fn estimate_underpayment_penalty(
+    input_info: &TaxInfo,
+    underpayment_info: &UnderpaymentInfo,
+) -> EstimaterResult<UnderpaymentPenalty> {
+    let current_year_tax = calculate_taxes_with_standard_deduction(input_info)?.federal_tax;
+    // Paying 90% of the current year's tax always avoids the penalty
+    let required_annual_payment = underpayment_info
+        .required_annual_payment
+        .min(round_to_hundredths(current_year_tax * 0.9));
+
+    let rate_table = InterestRateTable::from_data_file()?;
+    let mut quarters =
+        calculate_quarter_underpayments(required_annual_payment, underpayment_info, &rate_table)?;
+
+    if current_year_tax - underpayment_info.total_withholding() < MINIMUM_AMOUNT_OWED {
+        for quarter in quarters.iter_mut() {
+            quarter.penalty = 0.0;
+        }
+    }
+
+    Ok(UnderpaymentPenalty {
+        required_annual_payment,
+        quarters,
+    })
+}
+
+/// Applies each payment to the oldest unpaid installment and charges interest on every
+/// underpayment until it is paid, or until the return is due.
+fn calculate_quarter_underpayments(
+    required_annual_payment: f64,
+    underpayment_info: &UnderpaymentInfo,
+    rate_table: &InterestRateTable,
+) -> EstimaterResult<Vec<QuarterUnderpayment>> {
+    let tax_year = underpayment_info.tax_year;
+    let return_due_date = NaiveDate::from_ymd_opt(tax_year + 1, 4, 15)
+        .ok_or_else(|| EstimaterErrors::UserError(format!("Invalid tax year {tax_year}")))?;
+    let required_installment = round_to_hundredths(required_annual_payment / 4.0);
+    let withholding_per_quarter = underpayment_info.withholding / 4.0;
+
+    let mut payments = underpayment_info.payments.clone();
+    payments.sort_by_key(|payment| payment.date);
+    let mut payments = payments.into_iter().peekable();
+
+    let mut quarters: Vec<QuarterUnderpayment> = Vec::new();
+    let mut outstanding: Vec<OutstandingUnderpayment> = Vec::new();
+    let mut credit = 0.0;
+
+    for (quarter_index, due_date) in get_installment_due_dates(tax_year)?.into_iter().enumerate() {
+        while let Some(payment) = payments.next_if(|payment| payment.date <= due_date) {
+            credit += apply_payment(
+                payment.amount,
+                payment.date,
+                &mut outstanding,
+                &mut quarters,
+                rate_table,
+            )?;
+        }
+        credit += apply_payment(
+            withholding_per_quarter,
+            due_date,
+            &mut outstanding,
+            &mut quarters,
+            rate_table,
+        )?;
+
+        let paid = credit.min(required_installment);
+        credit -= paid;
+        let underpayment = round_to_hundredths(required_installment - paid);
+        if underpayment > 0.0 {
+            outstanding.push(OutstandingUnderpayment {
+                quarter_index,
+                amount: underpayment,
+                since: due_date,
+            });
+        }
+        quarters.push(QuarterUnderpayment {
+            due_date,
+            required_installment,
+            paid: round_to_hundredths(paid),
+            underpayment,
+            penalty: 0.0,
+        });
+    }
+
+    for payment in payments {
+        apply_payment(
+            payment.amount,
+            payment.date.min(return_due_date),
+            &mut outstanding,
+            &mut quarters,
+            rate_table,
+        )?;
+    }
+    for underpayment in outstanding {
+        quarters[underpayment.quarter_index].penalty += rate_table.calculate_interest(
+            underpayment.amount,
+            underpayment.since,
+            return_due_date,
+        )?;
+    }
+
+    for quarter in quarters.iter_mut() {
+        quarter.penalty = round_to_hundredths(quarter.penalty);
+    }
+    Ok(quarters)
+}
+
+/// Pays off the oldest underpayments first, charging the interest accrued until the payment.
+///
+/// # Return
+///
+/// * The portion of the payment left over after paying off all underpayments
+fn apply_payment(
+    mut amount: f64,
+    date: NaiveDate,
+    outstanding: &mut Vec<OutstandingUnderpayment>,
+    quarters: &mut [QuarterUnderpayment],
+    rate_table: &InterestRateTable,
+) -> EstimaterResult<f64> {
+    for underpayment in outstanding.iter_mut() {
+        if amount <= 0.0 {
+            break;
+        }
+        let paid_off = amount.min(underpayment.amount);
+        quarters[underpayment.quarter_index].penalty +=
+            rate_table.calculate_interest(paid_off, underpayment.since, date)?;
+        underpayment.amount -= paid_off;
+        amount -= paid_off;
+    }
+    outstanding.retain(|underpayment| underpayment.amount > 0.0);
+    Ok(amount)
+}
+
+impl InterestRateTable {
+    /// Attempts to read the data file containing the interest rates.
+    ///
+    /// # Return
+    ///
+    /// * Error if file doesn't exist (or something else)
+    /// * Success: InterestRateTable instance with rates sorted by start date.
+    pub(crate) fn from_data_file() -> EstimaterResult<Self> {
+        let mut table: InterestRateTable = read_data_file(INTEREST_RATE_FILE)?;
+        table.rates.sort_by_key(|rate| rate.start_date);
+        Ok(table)
+    }
+
+    /// Gets the yearly rate in effect on a given day
+    fn rate_on(&self, date: NaiveDate) -> EstimaterResult<f64> {
+        self.rates
+            .iter()
+            .rev()
+            .find(|rate| rate.start_date <= date)
+            .map(|rate| rate.annual_rate)
+            .ok_or_else(|| {
+                EstimaterErrors::ServerError(format!(
+                    "No underpayment interest rate is known for {date}"
+                ))
+            })
+    }
+
+    /// Calculates the interest charged on an amount from one date up to (but not including)
+    /// another. Interest is charged daily at the rate in effect on each day.
+    pub(crate) fn calculate_interest(
+        &self,
+        amount: f64,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> EstimaterResult<f64> {
+        let mut interest = 0.0;
+        let mut day = from;
+        while day < to {
+            let days_in_year = NaiveDate::from_ymd_opt(day.year(), 12, 31)
+                .map_or(365, |last_day| last_day.ordinal());
+            interest += amount * self.rate_on(day)? / days_in_year as f64;
+            day += Duration::days(1);
+        }
+        Ok(interest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::use_shipped_data;
+    use estimate_common::estimated_payments::PaymentRecord;
+
+    // A flat rate which charges exactly 0.01% a day in a non-leap year
+    fn help_make_rate_table() -> InterestRateTable {
+        InterestRateTable {
+            rates: vec![InterestRate {
+                start_date: NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(),
+                annual_rate: 0.0365,
+            }],
+        }
+    }
+
+    fn help_make_payment(month: u32, day: u32, amount: f64) -> PaymentRecord {
+        PaymentRecord {
+            date: NaiveDate::from_ymd_opt(2022, month, day).unwrap(),
+            amount,
+            is_withholding: false,
+        }
+    }
+
+    #[test]
+    fn test_deserializing_rates() {
+        use_shipped_data();
+        let table = InterestRateTable::from_data_file();
+        assert!(
+            table.is_ok(),
+            "table should be ok, but is {:?}",
+            table.err()
+        );
+    }
+
+    #[test]
+    fn test_required_payment_is_capped_by_current_year_tax() {
+        use_shipped_data();
+        let info = TaxInfo {
+            gross_yearly_income: 50000,
+            state_tax_rate_percent: 5.0,
+            ..Default::default()
+        };
+        let underpayment_info = UnderpaymentInfo {
+            tax_year: 2022,
+            required_annual_payment: 6000.0,
+            withholding: 0.0,
+            payments: vec![],
+        };
+        let penalty = estimate_underpayment_penalty(&info, &underpayment_info)
+            .expect("Estimating the penalty should've worked");
+        // 90% of 1027.5 + 12% of (50000 - 12950 - 10275)
+        assert_eq!(penalty.required_annual_payment, 3816.45);
+        assert!(penalty
+            .quarters
+            .iter()
+            .all(|quarter| quarter.underpayment > 0.0 && quarter.penalty > 0.0));
+    }
+
+    #[test]
+    fn test_calculate_quarter_underpayments() {
+        let info = UnderpaymentInfo {
+            tax_year: 2022,
+            required_annual_payment: 4000.0,
+            withholding: 0.0,
+            payments: vec![
+                help_make_payment(7, 15, 1000.0),
+                help_make_payment(4, 15, 1000.0),
+            ],
+        };
+        let quarters = calculate_quarter_underpayments(4000.0, &info, &help_make_rate_table())
+            .expect("Calculating underpayments should've worked");
+
+        let underpayments: Vec<f64> = quarters.iter().map(|q| q.underpayment).collect();
+        assert_eq!(underpayments, vec![0.0, 1000.0, 1000.0, 1000.0]);
+        // Q2 is paid 30 days late. Q3 and Q4 are never paid, so accrue until 2023-04-15.
+        let penalties: Vec<f64> = quarters.iter().map(|q| q.penalty).collect();
+        assert_eq!(penalties, vec![0.0, 3.0, 21.2, 8.9]);
+    }
+
+    #[test]
+    fn test_withholding_counts_as_paid_evenly() {
+        let info = UnderpaymentInfo {
+            tax_year: 2022,
+            required_annual_payment: 4000.0,
+            withholding: 4000.0,
+            payments: vec![],
+        };
+        let quarters = calculate_quarter_underpayments(4000.0, &info, &help_make_rate_table())
+            .expect("Calculating underpayments should've worked");
+        assert!(
+            quarters
+                .iter()
+                .all(|q| q.underpayment == 0.0 && q.penalty == 0.0),
+            "Withholding covering every installment should not be penalized"
+        );
+    }
+}
@@ -0,0 +1,3 @@
+//! Small helpers shared by the calculations across the server.
+
+pub(crate) use estimate_common::utils::round_to_hundredths;
@@ -0,0 +1,274 @@
+/// Calculates federal withholding from a 2020 or later Form W-4, using the percentage method of
+/// IRS Publication 15-T (Worksheet 1A), and recommends the extra withholding for a refund.
+use estimate_common::{
+    common::{FilingStatus, TaxInfo},
+    errors::{EstimaterErrors, EstimaterResult},
+    w4::{W4Analysis, W4Info},
+};
+
+use crate::{
+    server::{calculate_taxes_with_standard_deduction, get_federal_bracket_file},
+    standard_deduction::StandardDeductions,
+    supplemental::calculate_supplemental_withholding,
+    tax_bracket::TaxBrackets,
+    utils::round_to_hundredths,
+};
+
+/// Worksheet 1A line 1g when Step 2 is not checked, for married filing jointly
+const JOINT_WITHHOLDING_ADJUSTMENT: f64 = 12900.0;
+/// Worksheet 1A line 1g when Step 2 is not checked, for every other filing status
+const WITHHOLDING_ADJUSTMENT: f64 = 8600.0;
+
+/// Compares the federal withholding of a W-4 over the year to the federal tax, and finds the
+/// Step 4(c) extra withholding that reaches the target refund.
+///
+/// Only the salary and hourly wages, less the pre-tax deductions, are paid in regular paychecks.
+/// Supplemental wages (i.e. bonuses and RSU vests) are withheld at the flat supplemental rates.
+/// The federal tax is on the wages less the standard deduction the withholding assumes.
+///
+/// # Return
+///
+/// * `Error`: Some error explaining why the withholding or tax could not be calculated
+/// * `Ok(W4Analysis)`: The withholding of the W-4 and the recommended extra withholding
+pub fn analyze_w4(input_info: &TaxInfo, w4_info: &W4Info) -> EstimaterResult<W4Analysis> {
+    let periods = w4_info.pay_frequency.periods_per_year() as f64;
+    let wages_per_paycheck = round_to_hundredths(
+        (input_info.regular_wages() - input_info.total_pre_tax_deductions()) / periods,
+    );
+    if wages_per_paycheck < 0.0 {
+        return Err(EstimaterErrors::UserError(format!(
+            "The pre-tax deductions of {} are more than the regular wages of {}",
+            input_info.total_pre_tax_deductions(),
+            input_info.regular_wages()
+        )));
+    }
+
+    let tax_brackets =
+        TaxBrackets::from_data_file(get_federal_bracket_file(&input_info.filing_status))?;
+    let standard_deduction =
+        StandardDeductions::from_data_file()?.for_filing_status(&input_info.filing_status);
+    let withholding_before_extra = calculate_percentage_method_withholding(
+        &tax_brackets,
+        standard_deduction,
+        &input_info.filing_status,
+        w4_info,
+        wages_per_paycheck,
+    )?;
+    let withholding_per_paycheck =
+        round_to_hundredths(withholding_before_extra + w4_info.extra_withholding);
+
+    let supplemental_withholding =
+        calculate_supplemental_withholding(input_info.supplemental_wages());
+    let federal_tax = calculate_taxes_with_standard_deduction(input_info)?.federal_tax;
+
+    // The shortfall from the target without any extra withholding, spread over the paychecks
+    // and rounded up to the whole dollar as entered on the form
+    let refund_before_extra =
+        withholding_before_extra * periods + supplemental_withholding - federal_tax;
+    let recommended_extra_withholding = ((w4_info.target_refund - refund_before_extra) / periods)
+        .ceil()
+        .max(0.0);
+
+    Ok(W4Analysis {
+        pay_frequency: w4_info.pay_frequency,
+        withholding_per_paycheck,
+        supplemental_withholding,
+        annual_withholding: round_to_hundredths(
+            withholding_per_paycheck * periods + supplemental_withholding,
+        ),
+        federal_tax,
+        target_refund: w4_info.target_refund,
+        recommended_extra_withholding,
+    })
+}
+
+/// Calculates the federal withholding from a paycheck with Worksheet 1A, up to (but not
+/// including) the Step 4(c) extra withholding.
+///
+/// The percentage method tables are the federal brackets moved up by the standard deduction
+/// less the line 1g adjustment. With Step 2 checked, both the brackets and the standard
+/// deduction are halved, as each of the two jobs covers half of them.
+///
+/// # Params
+/// * `wages_per_paycheck` - The wages of the paycheck subject to federal income tax
+pub(crate) fn calculate_percentage_method_withholding(
+    tax_brackets: &TaxBrackets,
+    standard_deduction: f64,
+    filing_status: &FilingStatus,
+    w4_info: &W4Info,
+    wages_per_paycheck: f64,
+) -> EstimaterResult<f64> {
+    let periods = w4_info.pay_frequency.periods_per_year() as f64;
+
+    // Step 1: the adjusted annual wage amount
+    let annual_wages = wages_per_paycheck * periods + w4_info.other_income;
+    let adjustment = match (w4_info.multiple_jobs, filing_status) {
+        (true, _) => 0.0,
+        (false, FilingStatus::MarriedFilingJointly) => JOINT_WITHHOLDING_ADJUSTMENT,
+        (false, _) => WITHHOLDING_ADJUSTMENT,
+    };
+    let adjusted_annual_wages = (annual_wages - w4_info.deductions - adjustment).max(0.0);
+
+    // Step 2: the tentative withholding from the annual percentage method table
+    let tentative_annual_withholding = if w4_info.multiple_jobs {
+        let taxable = (adjusted_annual_wages - standard_deduction / 2.0).max(0.0);
+        tax_brackets.calculate_tax_amount(taxable * 2.0)? / 2.0
+    } else {
+        let taxable = (adjusted_annual_wages - (standard_deduction - adjustment)).max(0.0);
+        tax_brackets.calculate_tax_amount(taxable)?
+    };
+    let tentative_withholding = round_to_hundredths(tentative_annual_withholding / periods);
+
+    // Step 3: the credit for dependents
+    let credit_per_paycheck = round_to_hundredths(w4_info.dependents_credit / periods);
+    Ok((tentative_withholding - credit_per_paycheck).max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::data::use_shipped_data;
+    use estimate_common::paycheck::PayFrequency;
+
+    fn help_make_w4(multiple_jobs: bool) -> W4Info {
+        W4Info {
+            pay_frequency: PayFrequency::Weekly,
+            multiple_jobs,
+            ..Default::default()
+        }
+    }
+
+    fn help_calculate_withholding(
+        filing_status: FilingStatus,
+        w4_info: &W4Info,
+        wages_per_paycheck: f64,
+    ) -> f64 {
+        let tax_brackets =
+            TaxBrackets::from_data_file(get_federal_bracket_file(&filing_status)).unwrap();
+        let standard_deduction = StandardDeductions::from_data_file()
+            .unwrap()
+            .for_filing_status(&filing_status);
+        calculate_percentage_method_withholding(
+            &tax_brackets,
+            standard_deduction,
+            &filing_status,
+            w4_info,
+            wages_per_paycheck,
+        )
+        .expect("Withholding should've calculated")
+    }
+
+    #[test]
+    fn test_percentage_method_withholding() {
+        use_shipped_data();
+        // 52000 a year, less the 8600 adjustment, is 39050 above the 4350 the table starts at:
+        // 1027.5 + 12% of (39050 - 10275) = 4480.5 a year
+        let withholding =
+            help_calculate_withholding(FilingStatus::Single, &help_make_w4(false), 1000.0);
+        assert_eq!(withholding, 86.16);
+
+        // With Step 2 checked there is no adjustment, and half the standard deduction and
+        // brackets: (52000 - 6475) * 2 = 91050, taxed 15687.5, halved is 7843.75 a year
+        let withholding =
+            help_calculate_withholding(FilingStatus::Single, &help_make_w4(true), 1000.0);
+        assert_eq!(withholding, 150.84);
+
+        // Married filing jointly uses its own adjustment and table, which start at 13000
+        let withholding = help_calculate_withholding(
+            FilingStatus::MarriedFilingJointly,
+            &help_make_w4(false),
+            250.0,
+        );
+        assert_eq!(withholding, 0.0);
+    }
+
+    #[test]
+    fn test_percentage_method_steps_3_and_4() {
+        use_shipped_data();
+        let mut w4_info = help_make_w4(false);
+        w4_info.other_income = 5200.0;
+        w4_info.deductions = 5200.0;
+        assert_eq!(
+            help_calculate_withholding(FilingStatus::Single, &w4_info, 1000.0),
+            86.16
+        );
+
+        // The credit comes off each paycheck, but never below 0
+        w4_info.dependents_credit = 2000.0;
+        assert_eq!(
+            help_calculate_withholding(FilingStatus::Single, &w4_info, 1000.0),
+            86.16 - 38.46
+        );
+        w4_info.dependents_credit = 10000.0;
+        assert_eq!(
+            help_calculate_withholding(FilingStatus::Single, &w4_info, 1000.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_analyze_w4() {
+        use_shipped_data();
+        let info = TaxInfo {
+            gross_yearly_income: 52000,
+            ..Default::default()
+        };
+        let mut w4_info = help_make_w4(false);
+        let analysis = analyze_w4(&info, &w4_info).expect("W-4 analysis should've worked");
+        assert_eq!(analysis.withholding_per_paycheck, 86.16);
+        assert_eq!(analysis.annual_withholding, 4480.32);
+        // 1027.5 + 12% of (52000 - 12950 - 10275), which the withholding is within cents of
+        assert_eq!(analysis.federal_tax, 4480.5);
+        // Owing 0.18 over 52 paychecks rounds up to 1 more each
+        assert_eq!(analysis.recommended_extra_withholding, 1.0);
+
+        w4_info.extra_withholding = analysis.recommended_extra_withholding;
+        let analysis = analyze_w4(&info, &w4_info).expect("W-4 analysis should've worked");
+        assert_eq!(analysis.withholding_per_paycheck, 87.16);
+        assert!(analysis.projected_refund() >= 0.0);
+        assert_eq!(analysis.recommended_extra_withholding, 1.0);
+
+        // A refund of 1000 needs 1000.18 more over the 52 paychecks
+        w4_info.target_refund = 1000.0;
+        let analysis = analyze_w4(&info, &w4_info).expect("W-4 analysis should've worked");
+        assert_eq!(analysis.recommended_extra_withholding, 20.0);
+
+        // Any refund at all needs no extra withholding
+        w4_info.target_refund = -5000.0;
+        let analysis = analyze_w4(&info, &w4_info).expect("W-4 analysis should've worked");
+        assert_eq!(analysis.recommended_extra_withholding, 0.0);
+    }
+
+    #[test]
+    fn test_w4_withholding_has_no_refund() {
+        use_shipped_data();
+        let info = TaxInfo {
+            gross_yearly_income: 52000,
+            ..Default::default()
+        };
+        let analysis =
+            analyze_w4(&info, &help_make_w4(false)).expect("W-4 analysis should've worked");
+        let results = calculate_taxes_with_standard_deduction(&TaxInfo {
+            federal_withholding_ytd: analysis.annual_withholding,
+            ..info
+        })
+        .expect("Tax calculation should've worked");
+        // Withholding the W-4 amount all year leaves only the cents it rounds away
+        assert_eq!(results.federal_refund(), -0.18);
+    }
+
+    #[test]
+    fn test_analyze_w4_withholds_supplemental_wages() {
+        use_shipped_data();
+        let info = TaxInfo {
+            gross_yearly_income: 52000,
+            bonus_income: 10000.0,
+            ..Default::default()
+        };
+        let analysis =
+            analyze_w4(&info, &help_make_w4(false)).expect("W-4 analysis should've worked");
+        assert_eq!(analysis.supplemental_withholding, 2200.0);
+        assert_eq!(analysis.annual_withholding, 4480.32 + 2200.0);
+    }
+}